@@ -0,0 +1,424 @@
+//! Read-only JSON API over the stored ads, served by [`crate::server`].
+use crate::context::AppContext;
+use crate::database::{DbJobAd, GroupCount, JobField, JobListing, JobSort, ScrapeRun, SearchQuery};
+use crate::employers::{self, EmployerQuery, EmployerReport};
+use crate::find_jobs::Region;
+use crate::logging::error;
+use crate::municipality::municipalities_in;
+use crate::salary::{self, SalaryQuery, SalaryReport};
+use crate::skills::{self, RegionSkillQuery, RegionSkillReport, SkillQuery, SkillTrends};
+use crate::stats::{self, TrendQuery, Trends};
+use axum::extract::{Path, Query, Request, State};
+use axum::http::StatusCode;
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::str::FromStr;
+use strum_macros::Display;
+
+pub const DEFAULT_PAGE_SIZE: i64 = 50;
+pub const MAX_PAGE_SIZE: i64 = 500;
+const STATS_GROUP_LIMIT: i64 = 20;
+
+pub fn routes(ctx: &AppContext) -> Router<AppContext> {
+    Router::new()
+        .route("/jobs", get(list_jobs))
+        .route("/jobs/:id", get(get_job))
+        .route("/stats", get(stats))
+        .route("/stats/trends", get(trends))
+        .route("/stats/employers", get(employers))
+        .route("/stats/salaries", get(salaries))
+        .route("/stats/skills", get(skill_trends))
+        .route("/stats/skills/regions", get(region_skills))
+        .route_layer(middleware::from_fn_with_state(ctx.clone(), require_api_key))
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, Display)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+/// Query string of `GET /jobs` and the web UI. Text filters take comma separated values and
+/// match any of them, empty values are ignored.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ListParams {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub q: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+    /// Municipality concept ids such as `muSY_tsR_vDZ` or names such as `Lund`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub municipality: Option<String>,
+    /// Region names such as `Skåne`, matched through the municipalities of the region.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub occupation: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub company: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// Language codes, "sv", "en" or "other".
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// Work mode codes, "on_site", "hybrid" or "remote".
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub work_mode: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_after: Option<String>,
+    /// A date without a time includes the whole day.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_before: Option<String>,
+    /// Kronor per month, ads without a parsed salary are left out.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_salary: Option<String>,
+    #[serde(default)]
+    pub sort: JobSort,
+    #[serde(default)]
+    pub order: SortOrder,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<i64>,
+}
+
+fn non_empty(value: &Option<String>) -> Option<String> {
+    value
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+}
+
+fn split_values(values: &Option<String>) -> Vec<String> {
+    values
+        .as_deref()
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+impl ListParams {
+    /// Fails on region names that are not a [`Region`] and minimum salaries that are not a
+    /// number, which would otherwise match every ad.
+    pub fn to_listing(&self) -> Result<JobListing, String> {
+        let mut regions = Vec::new();
+        for name in split_values(&self.region) {
+            let region = Region::from_str(&name).map_err(|_| format!("Unknown region {}", name))?;
+            let municipalities = municipalities_in(&region);
+            if municipalities.is_empty() {
+                // Like bot watches, fall back to the region name in the workplace
+                regions.push(name);
+            }
+            regions.extend(
+                municipalities
+                    .iter()
+                    .map(|municipality| municipality.label.clone()),
+            );
+        }
+        let text_filters = [
+            (
+                split_values(&self.location),
+                SearchQuery::Location as fn(Vec<String>) -> SearchQuery,
+            ),
+            (split_values(&self.municipality), SearchQuery::Municipality),
+            (regions, SearchQuery::Location),
+            (split_values(&self.title), SearchQuery::Title),
+            (split_values(&self.occupation), SearchQuery::Occupation),
+            (split_values(&self.company), SearchQuery::Company),
+            (split_values(&self.source), SearchQuery::Source),
+            (split_values(&self.language), SearchQuery::Language),
+            (split_values(&self.work_mode), SearchQuery::WorkMode),
+        ];
+        let filters = text_filters
+            .into_iter()
+            .filter(|(values, _)| !values.is_empty())
+            .map(|(values, filter)| filter(values))
+            .collect();
+        let created_before = non_empty(&self.created_before).map(|date| {
+            if NaiveDate::parse_from_str(&date, "%Y-%m-%d").is_ok() {
+                format!("{}T23:59:59.999Z", date)
+            } else {
+                date
+            }
+        });
+
+        let min_salary = match non_empty(&self.min_salary) {
+            Some(salary) => Some(
+                salary
+                    .parse()
+                    .map_err(|_| format!("Invalid min_salary {}", salary))?,
+            ),
+            None => None,
+        };
+
+        Ok(JobListing {
+            filters,
+            text: non_empty(&self.q),
+            created_after: non_empty(&self.created_after),
+            created_before,
+            min_salary,
+            sort: self.sort,
+            descending: self.order == SortOrder::Desc,
+            limit: self
+                .limit
+                .unwrap_or(DEFAULT_PAGE_SIZE)
+                .clamp(1, MAX_PAGE_SIZE),
+            offset: self.offset.unwrap_or_default().max(0),
+        })
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct JobsResponse {
+    total: i64,
+    limit: i64,
+    offset: i64,
+    jobs: Vec<DbJobAd>,
+}
+
+#[derive(Debug, Serialize)]
+struct StatsResponse {
+    total: i64,
+    by_source: Vec<GroupCount>,
+    by_occupation: Vec<GroupCount>,
+    by_employer: Vec<GroupCount>,
+    last_successful_runs: Vec<ScrapeRun>,
+}
+
+fn error_response(status: StatusCode, message: &str) -> Response {
+    (status, Json(json!({ "error": message }))).into_response()
+}
+
+fn database_error(e: sqlx::Error) -> Response {
+    error!("REST API database error: {:?}", e);
+    error_response(StatusCode::INTERNAL_SERVER_ERROR, "Database error")
+}
+
+async fn require_api_key(State(ctx): State<AppContext>, request: Request, next: Next) -> Response {
+    let Some(expected) = ctx.config.api_key.as_deref() else {
+        return next.run(request).await;
+    };
+    let provided = request
+        .headers()
+        .get("x-api-key")
+        .and_then(|value| value.to_str().ok());
+    if provided == Some(expected) {
+        next.run(request).await
+    } else {
+        error_response(StatusCode::UNAUTHORIZED, "Missing or invalid API key")
+    }
+}
+
+async fn list_jobs(
+    State(ctx): State<AppContext>,
+    Query(params): Query<ListParams>,
+) -> Result<Json<JobsResponse>, Response> {
+    let listing = params
+        .to_listing()
+        .map_err(|message| error_response(StatusCode::BAD_REQUEST, &message))?;
+    let page = ctx
+        .store
+        .list_jobs(&listing)
+        .await
+        .map_err(database_error)?;
+    Ok(Json(JobsResponse {
+        total: page.total,
+        limit: listing.limit,
+        offset: listing.offset,
+        jobs: page.jobs,
+    }))
+}
+
+async fn get_job(
+    State(ctx): State<AppContext>,
+    Path(id): Path<String>,
+) -> Result<Json<DbJobAd>, Response> {
+    match ctx.store.get_job(&id).await.map_err(database_error)? {
+        Some(job) => Ok(Json(job)),
+        None => Err(error_response(StatusCode::NOT_FOUND, "No ad with that id")),
+    }
+}
+
+async fn stats(State(ctx): State<AppContext>) -> Result<Json<StatsResponse>, Response> {
+    let store = &ctx.store;
+    Ok(Json(StatsResponse {
+        total: store.count_jobs().await.map_err(database_error)?,
+        by_source: store
+            .count_jobs_by(JobField::Source, STATS_GROUP_LIMIT)
+            .await
+            .map_err(database_error)?,
+        by_occupation: store
+            .count_jobs_by(JobField::Occupation, STATS_GROUP_LIMIT)
+            .await
+            .map_err(database_error)?,
+        by_employer: store
+            .count_jobs_by(JobField::Employer, STATS_GROUP_LIMIT)
+            .await
+            .map_err(database_error)?,
+        last_successful_runs: store.last_successful_runs().await.map_err(database_error)?,
+    }))
+}
+
+async fn trends(
+    State(ctx): State<AppContext>,
+    Query(query): Query<TrendQuery>,
+) -> Result<Json<Trends>, Response> {
+    Ok(Json(
+        stats::trends(&ctx, &query).await.map_err(database_error)?,
+    ))
+}
+
+async fn employers(
+    State(ctx): State<AppContext>,
+    Query(query): Query<EmployerQuery>,
+) -> Result<Json<EmployerReport>, Response> {
+    Ok(Json(
+        employers::employer_report(&ctx, &query)
+            .await
+            .map_err(database_error)?,
+    ))
+}
+
+async fn salaries(
+    State(ctx): State<AppContext>,
+    Query(query): Query<SalaryQuery>,
+) -> Result<Json<SalaryReport>, Response> {
+    Ok(Json(
+        salary::salary_report(&ctx, &query)
+            .await
+            .map_err(database_error)?,
+    ))
+}
+
+async fn skill_trends(
+    State(ctx): State<AppContext>,
+    Query(query): Query<SkillQuery>,
+) -> Result<Json<SkillTrends>, Response> {
+    Ok(Json(
+        skills::skill_trends(&ctx, &query)
+            .await
+            .map_err(database_error)?,
+    ))
+}
+
+async fn region_skills(
+    State(ctx): State<AppContext>,
+    Query(query): Query<RegionSkillQuery>,
+) -> Result<Json<RegionSkillReport>, Response> {
+    Ok(Json(
+        skills::region_skills(&ctx, &query)
+            .await
+            .map_err(database_error)?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::{context, job};
+    use axum::body::{to_bytes, Body};
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    async fn get_json(app: Router, uri: &str, api_key: Option<&str>) -> (StatusCode, Value) {
+        let mut request = axum::http::Request::builder().uri(uri);
+        if let Some(key) = api_key {
+            request = request.header("x-api-key", key);
+        }
+        let response = app
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[test]
+    fn params_split_values_and_clamp_the_page() {
+        let listing = ListParams {
+            title: Some("rust, go,,".to_string()),
+            limit: Some(10_000),
+            offset: Some(-5),
+            ..Default::default()
+        }
+        .to_listing()
+        .unwrap();
+
+        assert_eq!(listing.filters.len(), 1);
+        assert!(
+            matches!(&listing.filters[0], SearchQuery::Title(titles) if titles == &["rust", "go"])
+        );
+        assert_eq!(listing.limit, MAX_PAGE_SIZE);
+        assert_eq!(listing.offset, 0);
+        assert!(listing.descending);
+    }
+
+    #[tokio::test]
+    async fn lists_and_fetches_jobs() {
+        let ctx = context().await;
+        ctx.store
+            .insert_jobs(&[
+                job("1").title("Rustutvecklare").build(),
+                job("2").title("Lärare").build(),
+                job("3").title("Senior Rustutvecklare").build(),
+            ])
+            .await
+            .unwrap();
+        let app = routes(&ctx).with_state(ctx);
+
+        let (status, body) = get_json(
+            app.clone(),
+            "/jobs?title=rust&sort=title&order=asc&limit=1",
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["total"], 2);
+        assert_eq!(body["jobs"][0]["id"], "1");
+
+        let (status, body) = get_json(app.clone(), "/jobs/2", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["title"], "Lärare");
+
+        let (status, _) = get_json(app.clone(), "/jobs/404", None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (status, body) = get_json(app.clone(), "/jobs?region=Atlantis", None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "Unknown region Atlantis");
+
+        let (status, body) = get_json(app, "/jobs?min_salary=40k", None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "Invalid min_salary 40k");
+    }
+
+    #[tokio::test]
+    async fn api_key_is_required_when_configured() {
+        let mut ctx = context().await;
+        let mut config = (*ctx.config).clone();
+        config.api_key = Some("hemlig".to_string());
+        ctx.config = std::sync::Arc::new(config);
+        let app = routes(&ctx).with_state(ctx);
+
+        let (status, _) = get_json(app.clone(), "/stats", None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, body) = get_json(app, "/stats", Some("hemlig")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["total"], 0);
+    }
+}
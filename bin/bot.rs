@@ -0,0 +1,15 @@
+use platsbanken::bot::run_bot;
+use platsbanken::context::{AppContext, Config};
+use platsbanken::logging::setup_log;
+use platsbanken::server;
+use platsbanken::taxonomy::init_taxonomy;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    setup_log().await?;
+    init_taxonomy()?;
+    let ctx = AppContext::new(Config::from_env()?).await?;
+    server::spawn(&ctx);
+    run_bot(ctx).await;
+    Ok(())
+}
@@ -1,25 +1,142 @@
-#![allow(dead_code)]
-use crate::constants;
-use crate::database::{store_user, DbJobAd, User};
+//! Telegram bot for watching and subscribing to job ads.
+use crate::context::AppContext;
+use crate::database::{DbJobAd, User};
 use crate::find_jobs::{OccupationType, Region};
-use crate::logging::info;
+use crate::language::Language;
+use crate::logging::error;
+use crate::metrics;
+use crate::municipality::{find_municipality, find_municipality_by_id};
+use crate::skills::{self, COMPOUND_HEADS};
+use crate::subscription::{self, DeliveryFrequency, CALLBACK_SHOW_MORE};
+use crate::work_mode::WorkMode;
 use serde::{Deserialize, Serialize};
-use std::str::FromStr;
 use strum::IntoEnumIterator;
 
 use std::collections::HashSet;
+use std::sync::Arc;
 
 use dotenv::dotenv;
 use teloxide::{
     prelude::*,
     types::{InlineKeyboardButton, InlineKeyboardMarkup},
+    update_listeners,
     utils::command::BotCommands,
+    RequestError,
 };
 
 #[derive(Default, Debug, Serialize, Deserialize)]
 pub struct UserSelections {
     pub selected_occupations: Option<HashSet<OccupationType>>,
     pub selected_regions: Option<HashSet<Region>>,
+    #[serde(default)]
+    pub selected_municipalities: Option<HashSet<String>>,
+    #[serde(default)]
+    pub keyword_watches: Option<Vec<KeywordWatch>>,
+}
+
+#[derive(Clone, Default, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeywordWatch {
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    pub employers: Vec<String>,
+    /// Kronor per month, only ads with a parsed salary of at least this much match.
+    #[serde(default)]
+    pub min_salary: Option<i64>,
+    /// Only ads detected to be in this language match.
+    #[serde(default)]
+    pub language: Option<Language>,
+    /// Only ads classified with this work mode match, `plats:distans` watches remote ads only.
+    #[serde(default)]
+    pub work_mode: Option<WorkMode>,
+}
+
+const KEYWORD_WATCH_USAGE: &str = "Ange minst ett nyckelord, en lägsta lön, ett språk eller en arbetsplats, t.ex. /nyckelord rust -senior @Spotify lön:40000 språk:en plats:distans";
+
+impl KeywordWatch {
+    // Parses "rust utvecklare -senior -konsult @Spotify lön:40000 språk:en plats:distans" into
+    // include, exclude, employer, minimum salary, language and work mode terms. The error is the
+    // reply to the user.
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let mut watch = KeywordWatch::default();
+        for term in input.split_whitespace() {
+            let lowercase = term.to_lowercase();
+            if let Some(amount) = lowercase.strip_prefix("lön:") {
+                let Ok(amount) = amount.replace(['.', '_'], "").parse() else {
+                    return Err(format!("Ogiltig lön {}, ange t.ex. lön:40000", amount));
+                };
+                watch.min_salary = Some(amount);
+            } else if let Some(code) = lowercase.strip_prefix("språk:") {
+                watch.language = Some(code.parse().map_err(|_| KEYWORD_WATCH_USAGE)?);
+            } else if let Some(mode) = lowercase.strip_prefix("plats:") {
+                watch.work_mode = Some(mode.parse().map_err(|_| KEYWORD_WATCH_USAGE)?);
+            } else if let Some(excluded) = lowercase.strip_prefix('-') {
+                watch.exclude.push(excluded.to_string());
+            } else if let Some(employer) = lowercase.strip_prefix('@') {
+                watch.employers.push(employer.replace('_', " "));
+            } else {
+                watch.include.push(lowercase);
+            }
+        }
+        watch.exclude.retain(|term| !term.is_empty());
+        watch.employers.retain(|term| !term.is_empty());
+
+        let has_filter =
+            watch.min_salary.is_some() || watch.language.is_some() || watch.work_mode.is_some();
+        if watch.include.is_empty() && watch.employers.is_empty() && !has_filter {
+            Err(KEYWORD_WATCH_USAGE.to_string())
+        } else {
+            Ok(watch)
+        }
+    }
+
+    pub fn matches(&self, job: &DbJobAd) -> bool {
+        let employer = job
+            .workplace_name
+            .as_deref()
+            .unwrap_or_default()
+            .to_lowercase();
+        let haystack = format!(
+            "{} {} {}",
+            job.title.as_deref().unwrap_or_default(),
+            employer,
+            job.description.as_deref().unwrap_or_default()
+        )
+        .to_lowercase();
+
+        // Whole words and compounds like skills, so "java" does not match "javascript"
+        let mentions = |term: &String| skills::mentions_word(&haystack, term, &COMPOUND_HEADS);
+        self.include.iter().all(mentions)
+            && !self.exclude.iter().any(mentions)
+            && (self.employers.is_empty()
+                || self
+                    .employers
+                    .iter()
+                    .any(|name| employer.contains(name.as_str())))
+            && self
+                .min_salary
+                .is_none_or(|min| job.salary_monthly.is_some_and(|salary| salary >= min))
+            && self.language.is_none_or(|language| {
+                job.language.as_deref() == Some(language.to_string().as_str())
+            })
+            && self.work_mode.is_none_or(|work_mode| {
+                job.work_mode.as_deref() == Some(work_mode.to_string().as_str())
+            })
+    }
+
+    pub fn as_readable_string(&self) -> String {
+        let mut terms = self.include.clone();
+        terms.extend(self.exclude.iter().map(|term| format!("-{}", term)));
+        terms.extend(
+            self.employers
+                .iter()
+                .map(|name| format!("@{}", name.replace(' ', "_"))),
+        );
+        terms.extend(self.min_salary.map(|salary| format!("lön:{}", salary)));
+        terms.extend(self.language.map(|language| format!("språk:{}", language)));
+        let work_mode = self.work_mode.map(WorkMode::keyword);
+        terms.extend(work_mode.map(|keyword| format!("plats:{}", keyword)));
+        terms.join(" ")
+    }
 }
 
 impl UserSelections {
@@ -27,16 +144,20 @@ impl UserSelections {
         Self {
             selected_occupations: Some(HashSet::new()),
             selected_regions: Some(HashSet::new()),
+            selected_municipalities: Some(HashSet::new()),
+            keyword_watches: Some(Vec::new()),
         }
     }
 
-    fn toggle(&mut self, occupation: OccupationType) {
-        if let Some(set) = self.selected_occupations.as_mut() {
-            if !set.insert(occupation.clone()) {
-                set.remove(&occupation);
-            }
+    // Returns true if the municipality was added, false if it was removed
+    fn toggle_municipality(&mut self, municipality_id: &str) -> bool {
+        let set = self
+            .selected_municipalities
+            .get_or_insert_with(HashSet::new);
+        if set.remove(municipality_id) {
+            false
         } else {
-            self.selected_occupations = Some(std::collections::HashSet::from([occupation]));
+            set.insert(municipality_id.to_string())
         }
     }
 
@@ -54,9 +175,46 @@ pub async fn setup_bot() -> Bot {
     Bot::from_env()
 }
 
-pub async fn run_bot() {
+pub async fn run_bot(ctx: AppContext) {
     let bot = setup_bot().await;
-    Command::repl(bot, answer).await;
+    let ctx = ctx.with_bot(bot.clone());
+    tokio::spawn(subscription::run_scheduler(ctx.clone()));
+
+    let handler = dptree::entry()
+        .branch(
+            Update::filter_message()
+                .filter_command::<Command>()
+                .endpoint(answer),
+        )
+        .branch(Update::filter_callback_query().endpoint(handle_callback));
+
+    // A bot that cannot reach Telegram at start up is reported as not polling by /readyz
+    let telegram = ctx.telegram.clone();
+    match bot.get_me().await {
+        Ok(_) => telegram.set_polling(true),
+        Err(e) => {
+            error!("Failed to reach Telegram: {:?}", e);
+            telegram.set_polling(false);
+        }
+    }
+
+    // Failed polls after the start are reported too, teloxide keeps retrying them
+    let listener = update_listeners::polling_default(bot.clone()).await;
+    let status = telegram.clone();
+    let listener_errors = move |e: RequestError| {
+        let status = status.clone();
+        async move {
+            error!("Failed to poll Telegram: {:?}", e);
+            status.record_error();
+        }
+    };
+    Dispatcher::builder(bot, handler)
+        .dependencies(dptree::deps![ctx])
+        .enable_ctrlc_handler()
+        .build()
+        .dispatch_with_listener(listener, Arc::new(listener_errors))
+        .await;
+    telegram.set_polling(false);
 }
 
 #[derive(BotCommands, Clone)]
@@ -70,17 +228,31 @@ enum Command {
     Bevaka,
     Bevakningar,
     Prenumeration,
+    Nyckelord(String),
+    Tabort(String),
+    Kommun(String),
+}
+
+// Sends an answer to a command or a button and counts it once Telegram has accepted it
+async fn send_reply(request: <Bot as Requester>::SendMessage) -> ResponseResult<Message> {
+    let message = request.await?;
+    metrics::BOT_MESSAGES_SENT
+        .with_label_values(&["reply"])
+        .inc();
+    Ok(message)
 }
 
-async fn answer(bot: Bot, msg: Message, cmd: Command) -> ResponseResult<()> {
+async fn answer(bot: Bot, msg: Message, cmd: Command, ctx: AppContext) -> ResponseResult<()> {
+    ctx.telegram.record_update();
     match cmd {
         Command::Start => {
             if let Some(user) = msg.from {
                 if user.is_bot {
                     return Ok(());
                 }
-                let user_id = user.id;
-                store_user(User::new(user_id.to_string())).await;
+                if let Err(e) = ctx.store.store_user(User::new(user.id.to_string())).await {
+                    error!("Failed to store user {}: {:?}", user.id, e);
+                }
             }
             let welcome_message = "👋 Välkommen till Platsbanken JobbBot! 🎉\n\n\
                                 Jag är här för att hjälpa dig att hitta de senaste jobbmöjligheterna från Platsbanken direkt i din Telegram-app. Här är vad jag kan göra:\n\n\
@@ -89,7 +261,7 @@ async fn answer(bot: Bot, msg: Message, cmd: Command) -> ResponseResult<()> {
                                 📅 Dagliga uppdateringar: Håll dig uppdaterad med de senaste jobben varje dag.\n\n\
                                 För att komma igång, skriv bara /hjälp för att se alla kommandon jag erbjuder!\n\n\
                                 Lycka till med ditt jobbsökande! 👩‍💻👨‍💻";
-            bot.send_message(msg.chat.id, welcome_message).await?
+            send_reply(bot.send_message(msg.chat.id, welcome_message)).await?
         }
         Command::Hjälp => {
             let descriptions = r#"
@@ -100,27 +272,49 @@ async fn answer(bot: Bot, msg: Message, cmd: Command) -> ResponseResult<()> {
             /bevaka - Börja bevaka något specifikt (t.ex. ett jobb) 👀
             /bevakningar - Visa alla dina aktiva bevakningar 📝
             /prenumeration - Prenumerera på uppdateringar och få notiser 🔔
+            /nyckelord - Bevaka nyckelord, t.ex. /nyckelord rust -senior -konsult @Spotify 🔎
+              Lägg till lön:40000 för annonser med minst 40 000 kr i månaden 💰
+              Lägg till språk:en för annonser på engelska (sv, en eller other) 🌍
+              Lägg till plats:distans för bara distansjobb (kontor, hybrid eller distans) 🏡
+            /kommun - Bevaka eller sluta bevaka en kommun, t.ex. /kommun Lund 🏘
+            /tabort - Ta bort en nyckelordsbevakning, t.ex. /tabort 1 🗑
 
             *Tips*: Skriv ett kommando och följ instruktionerna för att använda boten. Behöver du mer hjälp? Bara fråga mig! ✨
             "#;
 
             let escaped_descriptions = escape_markdown(descriptions);
 
-            bot.send_message(msg.chat.id, escaped_descriptions)
-                .parse_mode(teloxide::types::ParseMode::MarkdownV2)
-                .await?
+            send_reply(
+                bot.send_message(msg.chat.id, escaped_descriptions)
+                    .parse_mode(teloxide::types::ParseMode::MarkdownV2),
+            )
+            .await?
         }
 
         Command::Bevaka => {
-            let message = handle_bevaka(bot.clone(), msg.clone()).await?;
+            handle_bevaka(bot.clone(), msg.clone()).await?;
             return Ok(());
         }
 
         Command::Bevakningar => {
-            todo!();
+            handle_bevakningar(bot.clone(), &ctx, msg.clone()).await?;
+            return Ok(());
+        }
+        Command::Nyckelord(terms) => {
+            handle_nyckelord(bot.clone(), &ctx, msg.clone(), terms).await?;
+            return Ok(());
+        }
+        Command::Kommun(name) => {
+            handle_kommun(bot.clone(), &ctx, msg.clone(), name).await?;
+            return Ok(());
+        }
+        Command::Tabort(index) => {
+            handle_tabort(bot.clone(), &ctx, msg.clone(), index).await?;
+            return Ok(());
         }
         Command::Prenumeration => {
-            todo!();
+            handle_prenumeration(bot.clone(), msg.clone()).await?;
+            return Ok(());
         }
     };
 
@@ -158,14 +352,284 @@ async fn update_keyboard(
         .collect::<Vec<_>>();
 
     rows.push(vec![InlineKeyboardButton::callback("Klar ✅", "done")]);
-    println!("{user_selections:?}");
 
     let keyboard = InlineKeyboardMarkup::new(rows);
 
-    bot.send_message(msg.chat.id, "Välj yrkeskategori(er) du vill bevaka:")
-        .reply_markup(keyboard)
+    send_reply(
+        bot.send_message(msg.chat.id, "Välj yrkeskategori(er) du vill bevaka:")
+            .reply_markup(keyboard),
+    )
+    .await?;
+
+    Ok(())
+}
+
+// The sender of a message, a user that cannot be loaded is told to try again
+async fn load_user(bot: &Bot, ctx: &AppContext, msg: &Message) -> ResponseResult<Option<User>> {
+    let Some(user) = msg.from.as_ref() else {
+        return Ok(None);
+    };
+    let user_id = user.id.to_string();
+    match ctx.store.get_user(&user_id).await {
+        Ok(Some(user)) => Ok(Some(user)),
+        Ok(None) => Ok(Some(User::new(user_id))),
+        Err(e) => {
+            error!("Failed to load user {}: {:?}", user_id, e);
+            send_reply(bot.send_message(msg.chat.id, "Något gick fel, försök igen senare."))
+                .await?;
+            Ok(None)
+        }
+    }
+}
+
+async fn handle_nyckelord(
+    bot: Bot,
+    ctx: &AppContext,
+    msg: Message,
+    terms: String,
+) -> ResponseResult<()> {
+    let watch = match KeywordWatch::parse(&terms) {
+        Ok(watch) => watch,
+        Err(message) => {
+            send_reply(bot.send_message(msg.chat.id, message)).await?;
+            return Ok(());
+        }
+    };
+    let Some(mut user) = load_user(&bot, ctx, &msg).await? else {
+        return Ok(());
+    };
+
+    let watches = user.selections.keyword_watches.get_or_insert_with(Vec::new);
+    if !watches.contains(&watch) {
+        watches.push(watch.clone());
+    }
+    if let Err(e) = ctx.store.store_user(user).await {
+        error!("Failed to store keyword watch: {:?}", e);
+        send_reply(bot.send_message(msg.chat.id, "Något gick fel, försök igen senare.")).await?;
+        return Ok(());
+    }
+
+    send_reply(bot.send_message(
+        msg.chat.id,
+        format!("✅ Bevakar nu: {}", watch.as_readable_string()),
+    ))
+    .await?;
+    Ok(())
+}
+
+async fn handle_bevakningar(bot: Bot, ctx: &AppContext, msg: Message) -> ResponseResult<()> {
+    let Some(user) = load_user(&bot, ctx, &msg).await? else {
+        return Ok(());
+    };
+
+    let mut lines = Vec::new();
+    if let Some(occupations) = user
+        .selections
+        .selected_occupations
+        .filter(|o| !o.is_empty())
+    {
+        let names = occupations
+            .iter()
+            .map(|occupation| occupation.as_readable_string())
+            .collect::<Vec<_>>();
+        lines.push(format!("💼 Yrkeskategorier: {}", names.join(", ")));
+    }
+    if let Some(regions) = user.selections.selected_regions.filter(|r| !r.is_empty()) {
+        let names = regions
+            .iter()
+            .map(|region| format!("{:?}", region))
+            .collect::<Vec<_>>();
+        lines.push(format!("📍 Län: {}", names.join(", ")));
+    }
+    if let Some(municipalities) = user
+        .selections
+        .selected_municipalities
+        .filter(|m| !m.is_empty())
+    {
+        let names = municipalities
+            .iter()
+            .filter_map(|id| find_municipality_by_id(id))
+            .map(|municipality| municipality.label.clone())
+            .collect::<Vec<_>>();
+        lines.push(format!("🏘 Kommuner: {}", names.join(", ")));
+    }
+    for (index, watch) in user
+        .selections
+        .keyword_watches
+        .unwrap_or_default()
+        .iter()
+        .enumerate()
+    {
+        lines.push(format!("{}. 🔎 {}", index + 1, watch.as_readable_string()));
+    }
+
+    let message = if lines.is_empty() {
+        "Du har inga aktiva bevakningar. Använd /bevaka eller /nyckelord för att komma igång!"
+            .to_string()
+    } else {
+        format!("📝 Dina bevakningar:\n\n{}", lines.join("\n"))
+    };
+    send_reply(bot.send_message(msg.chat.id, message)).await?;
+    Ok(())
+}
+
+async fn handle_kommun(
+    bot: Bot,
+    ctx: &AppContext,
+    msg: Message,
+    name: String,
+) -> ResponseResult<()> {
+    let Some(municipality) = find_municipality(&name) else {
+        send_reply(bot.send_message(
+            msg.chat.id,
+            format!("Hittade ingen kommun som heter \"{}\"", name.trim()),
+        ))
+        .await?;
+        return Ok(());
+    };
+    let Some(mut user) = load_user(&bot, ctx, &msg).await? else {
+        return Ok(());
+    };
+
+    let added = user.selections.toggle_municipality(&municipality.id);
+    if let Err(e) = ctx.store.store_user(user).await {
+        error!("Failed to store municipality watch: {:?}", e);
+        send_reply(bot.send_message(msg.chat.id, "Något gick fel, försök igen senare.")).await?;
+        return Ok(());
+    }
+
+    let message = if added {
+        format!(
+            "✅ Bevakar nu {} ({:?})",
+            municipality.label, municipality.region
+        )
+    } else {
+        format!("🗑 Slutade bevaka {}", municipality.label)
+    };
+    send_reply(bot.send_message(msg.chat.id, message)).await?;
+    Ok(())
+}
+
+async fn handle_tabort(
+    bot: Bot,
+    ctx: &AppContext,
+    msg: Message,
+    index: String,
+) -> ResponseResult<()> {
+    let Some(mut user) = load_user(&bot, ctx, &msg).await? else {
+        return Ok(());
+    };
+    let watches = user.selections.keyword_watches.get_or_insert_with(Vec::new);
+
+    let removed = match index.trim().parse::<usize>() {
+        Ok(index) if index >= 1 && index <= watches.len() => watches.remove(index - 1),
+        _ => {
+            send_reply(bot.send_message(
+                msg.chat.id,
+                "Ange numret på bevakningen du vill ta bort, se /bevakningar",
+            ))
+            .await?;
+            return Ok(());
+        }
+    };
+    if let Err(e) = ctx.store.store_user(user).await {
+        error!("Failed to remove keyword watch: {:?}", e);
+        send_reply(bot.send_message(msg.chat.id, "Något gick fel, försök igen senare.")).await?;
+        return Ok(());
+    }
+
+    send_reply(bot.send_message(
+        msg.chat.id,
+        format!("🗑 Tog bort bevakningen: {}", removed.as_readable_string()),
+    ))
+    .await?;
+    Ok(())
+}
+
+async fn handle_prenumeration(bot: Bot, msg: Message) -> ResponseResult<()> {
+    let buttons = DeliveryFrequency::iter()
+        .map(|frequency| {
+            InlineKeyboardButton::callback(
+                frequency.as_readable_string(),
+                format!("sub:{}", frequency),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    send_reply(
+        bot.send_message(msg.chat.id, "Hur ofta vill du få nya annonser?")
+            .reply_markup(InlineKeyboardMarkup::new(vec![buttons])),
+    )
+    .await?;
+    Ok(())
+}
+
+fn hour_keyboard(frequency: DeliveryFrequency) -> InlineKeyboardMarkup {
+    let buttons = (6..=22)
+        .map(|hour| {
+            InlineKeyboardButton::callback(
+                format!("{:02}:00", hour),
+                format!("sub:{}:{}", frequency, hour),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    InlineKeyboardMarkup::new(buttons.chunks(4).map(|chunk| chunk.to_vec()))
+}
+
+async fn handle_callback(bot: Bot, q: CallbackQuery, ctx: AppContext) -> ResponseResult<()> {
+    ctx.telegram.record_update();
+    bot.answer_callback_query(q.id.clone()).await?;
+    let (Some(data), Some(message)) = (q.data.as_deref(), q.message.as_ref()) else {
+        return Ok(());
+    };
+    let chat_id = message.chat().id;
+
+    if data == CALLBACK_SHOW_MORE {
+        return subscription::send_next_page(&bot, chat_id).await;
+    }
+
+    let Some(subscription) = data.strip_prefix("sub:") else {
+        return Ok(());
+    };
+    let mut parts = subscription.split(':');
+    let Some(Ok(frequency)) = parts.next().map(str::parse::<DeliveryFrequency>) else {
+        return Ok(());
+    };
+    let delivery_hour = parts.next().and_then(|hour| hour.parse::<u32>().ok());
+
+    if frequency != DeliveryFrequency::Instant && delivery_hour.is_none() {
+        send_reply(
+            bot.send_message(chat_id, "Vilken tid vill du få dina annonser?")
+                .reply_markup(hour_keyboard(frequency)),
+        )
         .await?;
+        return Ok(());
+    }
+
+    if let Err(e) = ctx
+        .store
+        .store_subscription(&q.from.id.to_string(), frequency, delivery_hour)
+        .await
+    {
+        error!("Failed to store subscription for {}: {:?}", q.from.id, e);
+        send_reply(bot.send_message(chat_id, "Något gick fel, försök igen senare.")).await?;
+        return Ok(());
+    }
 
+    let confirmation = match (frequency, delivery_hour) {
+        (DeliveryFrequency::Weekly, Some(hour)) => {
+            format!(
+                "✅ Du får nu nya annonser en gång i veckan kl. {:02}:00.",
+                hour
+            )
+        }
+        (DeliveryFrequency::Daily, Some(hour)) => {
+            format!("✅ Du får nu nya annonser varje dag kl. {:02}:00.", hour)
+        }
+        _ => "✅ Du får nu nya annonser direkt när de publiceras.".to_string(),
+    };
+    send_reply(bot.send_message(chat_id, confirmation)).await?;
     Ok(())
 }
 
@@ -187,3 +651,77 @@ fn escape_markdown(text: &str) -> String {
         .replace('.', "\\.")
         .replace('!', "\\!")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::job;
+
+    #[test]
+    fn parses_keyword_watch_terms() {
+        let watch = KeywordWatch::parse("Rust Backend -Senior -konsult @Spotify_AB").unwrap();
+
+        assert_eq!(watch.include, vec!["rust", "backend"]);
+        assert_eq!(watch.exclude, vec!["senior", "konsult"]);
+        assert_eq!(watch.employers, vec!["spotify ab"]);
+        assert_eq!(
+            watch.as_readable_string(),
+            "rust backend -senior -konsult @spotify_ab"
+        );
+    }
+
+    #[test]
+    fn keyword_watch_needs_include_term_or_employer() {
+        assert!(KeywordWatch::parse("-senior -konsult").is_err());
+        assert!(KeywordWatch::parse("   ").is_err());
+        assert!(KeywordWatch::parse("@Klarna").is_ok());
+    }
+
+    #[test]
+    fn keyword_watch_filters_on_remote_work() {
+        let watch = KeywordWatch::parse("rust Plats:Distans").unwrap();
+        assert_eq!(watch.work_mode, Some(WorkMode::Remote));
+        assert_eq!(watch.as_readable_string(), "rust plats:distans");
+        assert!(KeywordWatch::parse("plats:distans").is_ok());
+        assert!(KeywordWatch::parse("rust plats:månen").is_err());
+
+        let mut job = job("1").title("Rustutvecklare").build_db();
+        assert!(!watch.matches(&job));
+        job.work_mode = Some("hybrid".to_string());
+        assert!(!watch.matches(&job));
+        job.work_mode = Some("remote".to_string());
+        assert!(watch.matches(&job));
+    }
+
+    #[test]
+    fn keyword_watch_filters_on_language() {
+        let watch = KeywordWatch::parse("Språk:EN").unwrap();
+        assert_eq!(watch.language, Some(Language::English));
+        assert_eq!(watch.as_readable_string(), "språk:en");
+        assert!(KeywordWatch::parse("rust språk:tyska").is_err());
+
+        let mut job = job("1").build_db();
+        assert!(!watch.matches(&job));
+        job.language = Some("en".to_string());
+        assert!(watch.matches(&job));
+    }
+
+    #[test]
+    fn keyword_watch_filters_on_monthly_salary() {
+        let watch = KeywordWatch::parse("rust Lön:40.000").unwrap();
+        assert_eq!(watch.min_salary, Some(40_000));
+        assert_eq!(watch.as_readable_string(), "rust lön:40000");
+        assert!(KeywordWatch::parse("lön:40000").is_ok());
+        assert_eq!(
+            KeywordWatch::parse("rust Lön:40k").unwrap_err(),
+            "Ogiltig lön 40k, ange t.ex. lön:40000"
+        );
+
+        let mut job = job("1").title("Rustutvecklare").build_db();
+        assert!(!watch.matches(&job));
+        job.salary_monthly = Some(45_000);
+        assert!(watch.matches(&job));
+        job.salary_monthly = Some(35_000);
+        assert!(!watch.matches(&job));
+    }
+}
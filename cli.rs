@@ -0,0 +1,125 @@
+use clap::{Parser, Subcommand};
+use platsbanken::skills::SkillQuery;
+use platsbanken::stats::{self, Dimension, Interval, OutputFormat};
+use platsbanken::{employers, salary, skills, OccupationType, Region, SearchDuration};
+use std::path::PathBuf;
+
+#[derive(Parser, Debug)]
+#[command(name = "platsbanken", about = "Scrapes job ads from Platsbanken")]
+pub struct Cli {
+    /// Save every search request and raw response to this directory
+    #[arg(long, global = true, conflicts_with = "replay")]
+    pub record: Option<PathBuf>,
+    /// Serve search responses from a recorded directory instead of the network
+    #[arg(long, global = true)]
+    pub replay: Option<PathBuf>,
+    #[command(subcommand)]
+    pub command: Option<Commands>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Commands {
+    /// Fetch occupations, regions and municipalities from the JobTech taxonomy
+    RefreshTaxonomy,
+    /// Scrape ads, optionally limited to an occupation field, region, municipality or free text
+    Scrape {
+        #[arg(long, default_value = "daily")]
+        duration: SearchDuration,
+        #[arg(long)]
+        occupation: Option<OccupationType>,
+        #[arg(long)]
+        region: Option<Region>,
+        #[arg(long)]
+        municipality: Option<String>,
+        #[arg(long)]
+        freetext: Option<String>,
+        /// Number of ads to fetch descriptions and salaries for after the scrape, one request
+        /// per ad
+        #[arg(long, default_value_t = 0)]
+        details: i64,
+    },
+    /// Show the most recent scrape runs
+    Runs {
+        #[arg(long, default_value_t = 20)]
+        limit: i64,
+    },
+    /// Fetch descriptions and salaries of stored ads, one request per ad
+    Details {
+        #[arg(long, default_value_t = 500)]
+        limit: i64,
+    },
+    /// Show new ads per day or week by occupation field, region, source or employer
+    Stats {
+        #[arg(long, default_value_t = Dimension::default())]
+        by: Dimension,
+        #[arg(long, default_value_t = Interval::default())]
+        interval: Interval,
+        /// Number of periods up to and including the current one
+        #[arg(long, default_value_t = stats::DEFAULT_PERIODS)]
+        periods: u32,
+        /// Number of values with the most ads to show
+        #[arg(long, default_value_t = stats::DEFAULT_TOP)]
+        top: usize,
+        #[arg(long, default_value_t = OutputFormat::default())]
+        format: OutputFormat,
+    },
+    /// Show how many new ads ask for each skill per day or week, optionally in one region
+    Skills {
+        #[arg(long, default_value_t = SkillQuery::default().interval)]
+        interval: Interval,
+        /// Number of periods up to and including the current one
+        #[arg(long, default_value_t = skills::DEFAULT_PERIODS)]
+        periods: u32,
+        /// Number of skills asked for in the most ads to show
+        #[arg(long, default_value_t = skills::DEFAULT_TOP)]
+        top: usize,
+        #[arg(long)]
+        region: Option<Region>,
+        #[arg(long, default_value_t = OutputFormat::default())]
+        format: OutputFormat,
+    },
+    /// Show the skills asked for in the most ads of each region
+    SkillRegions {
+        /// Length of the period in days
+        #[arg(long, default_value_t = skills::DEFAULT_REGION_PERIOD_DAYS)]
+        days: i64,
+        /// Number of skills per region
+        #[arg(long, default_value_t = skills::DEFAULT_REGION_TOP)]
+        top: usize,
+        #[arg(long, default_value_t = OutputFormat::default())]
+        format: OutputFormat,
+    },
+    /// Extract the skills of every stored ad again, after changing the skill dictionary
+    ExtractSkills,
+    /// Detect the language of every stored ad again
+    DetectLanguages,
+    /// Classify every stored ad as on-site, hybrid or remote work again
+    ClassifyWorkModes,
+    /// Show monthly salaries of the ads with a parsed salary by occupation field, region, source
+    /// or employer
+    Salaries {
+        #[arg(long, default_value_t = Dimension::default())]
+        by: Dimension,
+        /// Length of the period in days
+        #[arg(long, default_value_t = salary::DEFAULT_PERIOD_DAYS)]
+        days: i64,
+        #[arg(long, default_value_t = salary::DEFAULT_TOP)]
+        top: usize,
+        #[arg(long, default_value_t = OutputFormat::default())]
+        format: OutputFormat,
+    },
+    /// Show the most active employers, their open ads, reposts and time live, and new employers
+    Employers {
+        /// Length of the period in days
+        #[arg(long, default_value_t = employers::DEFAULT_PERIOD_DAYS)]
+        days: i64,
+        #[arg(long, default_value_t = employers::DEFAULT_TOP)]
+        top: usize,
+        #[arg(long)]
+        region: Option<Region>,
+        #[arg(long)]
+        occupation: Option<OccupationType>,
+        #[arg(long, default_value_t = OutputFormat::default())]
+        format: OutputFormat,
+    },
+}
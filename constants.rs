@@ -1,6 +1,16 @@
-pub const URL_SEARCH: &str = "https://platsbanken-api.arbetsformedlingen.se/jobs/v1/search";
+//! API endpoints and scraping limits.
+pub const URL_API: &str = "https://platsbanken-api.arbetsformedlingen.se/jobs/v1";
+pub const API_URL_ENV: &str = "PLATSBANKEN_API_URL";
 pub const URL_JOB_ADS: &str = "https://arbetsformedlingen.se/platsbanken/annonser/";
 pub const MAX_RECORDS: usize = 2000;
 pub const PAGE_SIZE: usize = 100;
 pub const SOURCE_PB: &str = "pb";
 pub const SOURCE_EXTERNAL: &str = "joblinks";
+pub const URL_TAXONOMY_GRAPHQL: &str = "https://taxonomy.api.jobtechdev.se/v1/taxonomy/graphql";
+pub const TAXONOMY_PATH: &str = "data/taxonomy.json";
+pub const SKILLS_PATH: &str = "data/skills.json";
+pub const INSERT_BATCH_SIZE: usize = 500;
+pub const PIPELINE_CAPACITY: usize = 8;
+pub const SCRAPE_MAX_AGE_HOURS: i64 = 26;
+pub const TELEGRAM_ERROR_MAX_AGE_MINUTES: i64 = 5;
+pub const MAX_DETAILS_ATTEMPTS: i64 = 3;
@@ -0,0 +1,87 @@
+//! Shared state handed to the scraper, the bot and the exporters.
+use crate::constants::{API_URL_ENV, URL_API};
+use crate::fixtures::FixtureMode;
+use crate::health::TelegramStatus;
+use crate::store::{self, JobStore};
+use reqwest::Client;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use teloxide::Bot;
+
+#[derive(Clone, Debug)]
+pub struct Config {
+    pub database_url: String,
+    pub api_url: String,
+    pub fixture_mode: FixtureMode,
+    /// Address for the HTTP endpoints in [`crate::server`], they are not served when unset.
+    pub http_addr: Option<SocketAddr>,
+    /// Key required in the `x-api-key` header of REST API requests, the API is open when unset.
+    pub api_key: Option<String>,
+}
+
+impl Config {
+    /// Reads `DATABASE_URL`, `PLATSBANKEN_API_URL`, `HTTP_ADDR` and `API_KEY`, falling back to
+    /// the defaults. An `HTTP_ADDR` that is not a socket address is an error rather than a
+    /// reason to silently skip the HTTP endpoints.
+    pub fn from_env() -> Result<Self, Box<dyn std::error::Error>> {
+        let http_addr = match std::env::var("HTTP_ADDR") {
+            Ok(addr) => Some(
+                addr.parse()
+                    .map_err(|e| format!("Invalid HTTP_ADDR {:?}: {}", addr, e))?,
+            ),
+            Err(_) => None,
+        };
+        Ok(Self {
+            database_url: std::env::var("DATABASE_URL")
+                .unwrap_or_else(|_| "sqlite://database.db".to_string()),
+            api_url: std::env::var(API_URL_ENV).unwrap_or_else(|_| URL_API.to_string()),
+            fixture_mode: FixtureMode::Live,
+            http_addr,
+            api_key: std::env::var("API_KEY").ok().filter(|key| !key.is_empty()),
+        })
+    }
+}
+
+#[derive(Clone)]
+pub struct AppContext {
+    pub store: Arc<dyn JobStore>,
+    pub client: Client,
+    pub config: Arc<Config>,
+    pub telegram: Arc<TelegramStatus>,
+    /// Telegram bot for messages sent outside of update handlers, such as digests. None when
+    /// no bot runs.
+    pub bot: Option<Bot>,
+}
+
+impl AppContext {
+    /// Connects to the database in `config` and applies its migrations.
+    pub async fn new(config: Config) -> Result<Self, sqlx::Error> {
+        let store = store::connect(&config.database_url).await?;
+        Ok(Self::with_store(store, config))
+    }
+
+    pub fn with_store(store: Arc<dyn JobStore>, config: Config) -> Self {
+        Self {
+            store,
+            client: Client::new(),
+            config: Arc::new(config),
+            telegram: Arc::new(TelegramStatus::default()),
+            bot: None,
+        }
+    }
+
+    pub fn with_bot(self, bot: Bot) -> Self {
+        Self {
+            bot: Some(bot),
+            ..self
+        }
+    }
+
+    pub fn search_url(&self) -> String {
+        format!("{}/search", self.config.api_url)
+    }
+
+    pub fn job_details_url(&self, id: &str) -> String {
+        format!("{}/job/{}", self.config.api_url, id)
+    }
+}
@@ -1,18 +1,14 @@
-#![allow(dead_code)]
-use csv::Writer;
-use futures::future::select;
-use sqlx::{Error, FromRow, Row, SqlitePool};
-use std::path::Path;
-use tokio::sync::OnceCell;
-
-use crate::find_jobs::JobAd;
+//! Storage for job ads and bot users.
+use crate::bot::UserSelections;
 use crate::logging::*;
-use crate::{bot::UserSelections, find_jobs::OccupationType};
+use crate::store::JobStore;
+use crate::subscription::DeliveryFrequency;
+use chrono::Duration;
 use serde::{Deserialize, Serialize};
+use strum_macros::Display;
 
-static POOL: OnceCell<SqlitePool> = OnceCell::const_new();
-
-#[derive(Clone, Serialize, Deserialize, Debug)]
+/// A job ad as stored in the `jobs` table.
+#[derive(Clone, Default, Serialize, Deserialize, Debug, sqlx::FromRow)]
 pub struct DbJobAd {
     pub id: Option<String>,
     pub title: Option<String>,
@@ -30,9 +26,26 @@ pub struct DbJobAd {
     pub url: Option<String>,
     #[serde(skip_deserializing)]
     pub created_at: Option<String>,
-}
-pub trait SaveToCsv {
-    fn save_to_csv<P: AsRef<Path>>(&self, file_path: P) -> Result<(), Error>;
+    pub description: Option<String>,
+    /// Parsed salary, see [`crate::salary::Salary`].
+    #[serde(skip_deserializing)]
+    pub salary_type: Option<String>,
+    #[serde(skip_deserializing)]
+    pub salary_description: Option<String>,
+    #[serde(skip_deserializing)]
+    pub salary_min: Option<i64>,
+    #[serde(skip_deserializing)]
+    pub salary_max: Option<i64>,
+    #[serde(skip_deserializing)]
+    pub salary_period: Option<String>,
+    #[serde(skip_deserializing)]
+    pub salary_monthly: Option<i64>,
+    /// "sv", "en" or "other", see [`crate::language::Language`].
+    #[serde(skip_deserializing)]
+    pub language: Option<String>,
+    /// "on_site", "hybrid" or "remote", see [`crate::work_mode::WorkMode`].
+    #[serde(skip_deserializing)]
+    pub work_mode: Option<String>,
 }
 
 #[derive(Debug, Default)]
@@ -41,6 +54,15 @@ pub struct User {
     pub selections: UserSelections,
 }
 
+#[derive(Debug)]
+pub struct Subscriber {
+    pub user_id: String,
+    pub selections: UserSelections,
+    pub frequency: DeliveryFrequency,
+    pub delivery_hour: Option<u32>,
+    pub last_delivered_at: Option<String>,
+}
+
 impl User {
     pub fn new(id: String) -> Self {
         Self {
@@ -50,236 +72,190 @@ impl User {
     }
 }
 
-impl<'r> FromRow<'r, sqlx::sqlite::SqliteRow> for DbJobAd {
-    fn from_row(row: &'r sqlx::sqlite::SqliteRow) -> Result<Self, sqlx::Error> {
-        Ok(DbJobAd {
-            id: row.try_get("id")?,
-            title: row.try_get("title")?,
-            occupation: row.try_get("occupation")?,
-            workplace: row.try_get("workplace")?,
-            workplace_name: row.try_get("workplace_name")?,
-            published_date: row.try_get("published_date")?,
-            last_application_date: row.try_get("last_application_date")?,
-            source: row.try_get("source")?,
-            url: row.try_get("url")?,
-            created_at: row.try_get("created_at")?,
-        })
-    }
+/// Ads written by [`JobStore::upsert_jobs`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct WrittenAds {
+    pub inserted: u64,
+    /// Ads that were already stored and got the scraped values.
+    pub updated: u64,
 }
 
-#[derive(Debug)]
+/// One scrape of a source as recorded in the `scrape_runs` table. Runs that never finished
+/// have no `finished_at`.
+#[derive(Clone, Debug, Default, Serialize, sqlx::FromRow)]
+pub struct ScrapeRun {
+    pub id: i64,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+    pub source: String,
+    pub filters: Option<String>,
+    pub pages_fetched: i64,
+    pub ads_received: i64,
+    /// Sum of `numberOfAds` reported by the first page of every search.
+    pub ads_available: i64,
+    pub ads_inserted: i64,
+    pub ads_updated: i64,
+    pub error: Option<String>,
+}
+
+/// Filters for [`JobStore::query_jobs`], text filters match any of the values ignoring case.
+#[derive(Clone, Debug)]
 pub enum SearchQuery {
     Location(Vec<String>),
+    /// Municipality concept ids or names, matched against the whole workplace.
+    Municipality(Vec<String>),
     Title(Vec<String>),
     Occupation(Vec<String>),
     Company(Vec<String>),
-    MostRecent(i32),
+    Source(Vec<String>),
+    /// Language codes of [`crate::language::Language`], matched exactly.
+    Language(Vec<String>),
+    /// Codes of [`crate::work_mode::WorkMode`], matched exactly.
+    WorkMode(Vec<String>),
+    /// Ads created within the duration before now.
+    MostRecent(Duration),
     All,
 }
 
-pub async fn connect_database() -> Result<(), sqlx::Error> {
-    let database_url = std::env::var("DATABASE_URL").unwrap_or_else(|_| "sqlite://database.db".to_string());
-    let pool = SqlitePool::connect(&database_url).await?;
-    POOL.set(pool)
-        .map_err(|_| sqlx::Error::Protocol("Database pool initialization failed".into()))?;
-    info!("Database initialized!");
-    Ok(())
+/// Column a listing of ads is sorted on.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, Display)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum JobSort {
+    #[default]
+    Published,
+    LastApplication,
+    Created,
+    Title,
+    Employer,
+    Workplace,
+    Occupation,
+    Source,
 }
 
-pub async fn insert_jobs(job_ads: Vec<JobAd>) -> Result<(), sqlx::Error> {
-    let pool = POOL.get().expect("Database pool is not initialised");
-
-    let mut transaction = pool.begin().await?;
-
-    for job in &job_ads {
-        let query = r#"
-            INSERT OR IGNORE INTO jobs (id, title, occupation, workplace, workplace_name, published_date, last_application_date, source, url, created_at)
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
-        "#;
-
-        sqlx::query(query)
-            .bind(&job.id)
-            .bind(&job.title)
-            .bind(&job.occupation)
-            .bind(&job.workplace)
-            .bind(&job.workplace_name)
-            .bind(&job.published_date.as_deref())
-            .bind(&job.last_application_date.as_deref())
-            .bind(&job.source)
-            .bind(&job.url)
-            .bind(&job.created_at)
-            .execute(&mut *transaction)
-            .await?;
+impl JobSort {
+    pub fn column(&self) -> &'static str {
+        match self {
+            JobSort::Published => "published_date",
+            JobSort::LastApplication => "last_application_date",
+            JobSort::Created => "created_at",
+            JobSort::Title => "title",
+            JobSort::Employer => "workplace_name",
+            JobSort::Workplace => "workplace",
+            JobSort::Occupation => "occupation",
+            JobSort::Source => "source",
+        }
     }
-    transaction.commit().await?;
-
-    info!("Inserted {} entries to database!", job_ads.len());
-    Ok(())
 }
 
-/////////////////////// USER FUNCTIONS //////////////////////
-/////////////////////// USER FUNCTIONS //////////////////////
-/////////////////////// USER FUNCTIONS //////////////////////
-
-pub async fn store_user(user: User) -> Result<(), sqlx::Error> {
-    let pool = POOL.get().expect("Database pool is not initialised");
-    let occupations_json = user
-        .selections
-        .selected_occupations
-        .map(|occupations| serde_json::to_string(&occupations).unwrap_or_default());
-
-    let regions_json = user
-        .selections
-        .selected_regions
-        .map(|regions| serde_json::to_string(&regions).unwrap_or_default());
-    let query = sqlx::query!(
-        r#"
-        INSERT INTO users (id, jobcategories, regions)
-        VALUES ($1, $2, $3)
-        ON CONFLICT (id)
-        DO UPDATE SET
-            jobcategories = CASE WHEN EXCLUDED.jobcategories IS NOT NULL THEN EXCLUDED.jobcategories ELSE users.jobcategories END,
-            regions = CASE WHEN EXCLUDED.regions IS NOT NULL THEN EXCLUDED.regions ELSE users.regions END
-        "#,
-        user.id,
-        occupations_json,
-        regions_json
-    );
-
-    info!("Inserting or updating user {} in database!", user.id);
-    query.execute(pool).await?;
+/// One page of stored ads. Every filter has to match, see [`JobStore::list_jobs`].
+#[derive(Clone, Debug, Default)]
+pub struct JobListing {
+    pub filters: Vec<SearchQuery>,
+    /// Free text matched against the title, employer, occupation and description.
+    pub text: Option<String>,
+    pub created_after: Option<String>,
+    pub created_before: Option<String>,
+    /// Kronor per month the upper end of the salary has to reach, ads without one are left out.
+    pub min_salary: Option<i64>,
+    pub sort: JobSort,
+    pub descending: bool,
+    pub limit: i64,
+    pub offset: i64,
+}
 
-    Ok(())
+#[derive(Debug, Serialize)]
+pub struct JobPage {
+    /// Number of ads matching the filters on all pages.
+    pub total: i64,
+    pub jobs: Vec<DbJobAd>,
 }
 
-pub async fn get_database_entries_count() -> Result<i64, sqlx::Error> {
-    let pool = POOL.get().expect("Database pool is not initialized");
-    let result = sqlx::query!(
-        r#"
-SELECT COUNT(*) as count
-FROM jobs
-"#
-    )
-    .fetch_one(pool)
-    .await?;
-    Ok(result.count)
+/// Column ads are grouped on by [`JobStore::count_jobs_by`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JobField {
+    Source,
+    Occupation,
+    Workplace,
+    Employer,
 }
 
-pub async fn print_database_entries_count() {
-    let pool = POOL.get().expect("Database pool is not initialized");
-    let result = sqlx::query!(
-        r#"
-SELECT COUNT(*) as count
-FROM jobs
-"#
-    )
-    .fetch_one(pool)
-    .await;
-    match result {
-        Ok(record) => {
-            let count = record.count;
-            info!("There is {} entries in database!", count)
-        }
-        Err(e) => {
-            error!("Error printing database count! {}", e)
+impl JobField {
+    pub fn column(&self) -> &'static str {
+        match self {
+            JobField::Source => "source",
+            JobField::Occupation => "occupation",
+            JobField::Workplace => "workplace",
+            JobField::Employer => "workplace_name",
         }
     }
 }
 
-pub async fn get_jobs_by_query(query: SearchQuery) -> Result<Vec<DbJobAd>, sqlx::Error> {
-    let pool = POOL.get().expect("Database pool is not initialized");
-
-    if let SearchQuery::All = query {
-        return sqlx::query_as!(
-            DbJobAd,
-            r#"
-        SELECT *
-        FROM jobs
-        "#
-        )
-        .fetch_all(pool)
-        .await;
-    }
-
-    if let SearchQuery::MostRecent(timestamp) = query {
-        return sqlx::query_as!(
-            DbJobAd,
-            r#"
-            SELECT *
-            FROM jobs
-            WHERE created_at >= $1
-            "#,
-            timestamp
-        )
-        .fetch_all(pool)
-        .await;
-    }
-
-    let (field, values) = match query {
-        SearchQuery::Location(locations) => ("workplace", locations),
-        SearchQuery::Title(titles) => ("title", titles),
-        SearchQuery::Occupation(occupations) => ("occupation", occupations),
-        SearchQuery::Company(companies) => ("workplace_name", companies),
-        _ => unreachable!(),
-    };
+#[derive(Clone, Debug, Serialize, sqlx::FromRow)]
+pub struct GroupCount {
+    pub value: Option<String>,
+    pub count: i64,
+}
 
-    let placeholders = values
-        .iter()
-        .map(|_| format!("{} LIKE ?", field))
-        .collect::<Vec<String>>()
-        .join(" OR ");
-    let query = format!(
-        r#"
-        SELECT *
-        FROM jobs
-        WHERE {}
-        "#,
-        placeholders
-    );
-    let mut query_builder = sqlx::query_as::<_, DbJobAd>(&query);
-    for value in values {
-        let like_value = format!("%{}%", value);
-        query_builder = query_builder.bind(like_value);
-    }
+/// New ads per creation day and attribute combination, the input of [`crate::stats`].
+#[derive(Clone, Debug, Default, sqlx::FromRow)]
+pub struct DailyCount {
+    /// Creation date as `YYYY-MM-DD`.
+    pub day: String,
+    pub occupation: Option<String>,
+    pub workplace: Option<String>,
+    pub source: Option<String>,
+    pub employer: Option<String>,
+    pub count: i64,
+}
 
-    let jobs = query_builder.fetch_all(pool).await;
-    match &jobs {
-        Ok(jobs_list) => {
-            info!("Fetched {} amount of jobs", jobs_list.len());
-        }
-        Err(e) => {
-            error!("Error fetching jobs {:?}", e);
-        }
-    }
+/// Fields of an ad used by the employer report in [`crate::employers`].
+#[derive(Clone, Debug, Default, sqlx::FromRow)]
+pub struct EmployerAd {
+    pub id: String,
+    pub employer: String,
+    pub title: Option<String>,
+    pub occupation: Option<String>,
+    pub workplace: Option<String>,
+    pub created_at: Option<String>,
+    /// Latest scrape that returned the ad.
+    pub last_seen_at: Option<String>,
+    pub last_application_date: Option<String>,
+}
 
-    jobs.map_err(|e| {
-        error!("Error fetching jobs: {:?}", e);
-        e
-    })
+/// Monthly salary of an ad with the attributes salaries are grouped on in [`crate::salary`].
+#[derive(Clone, Debug, Default, sqlx::FromRow)]
+pub struct SalaryRow {
+    pub occupation: Option<String>,
+    pub workplace: Option<String>,
+    pub source: Option<String>,
+    pub employer: Option<String>,
+    pub salary_monthly: i64,
 }
 
-//////////////////////// SAVING TO CSV /////////////////
-//////////////////////// SAVING TO CSV /////////////////
-//////////////////////// SAVING TO CSV /////////////////
-//////////////////////// SAVING TO CSV /////////////////
+/// A skill mentioned in an ad, see [`crate::skills`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, sqlx::FromRow)]
+pub struct JobSkill {
+    pub job_id: String,
+    pub skill: String,
+}
 
-impl SaveToCsv for Vec<DbJobAd> {
-    fn save_to_csv<P: AsRef<Path>>(&self, file_path: P) -> Result<(), Error> {
-        let file_path = file_path.as_ref();
-        let mut writer = Writer::from_path(file_path)
-            .map_err(|e| Error::Protocol(format!("Failed to create CSV file: {:?}", e).into()))?;
+/// Ads asking for a skill created on one day with the same workplace.
+#[derive(Clone, Debug, Default, sqlx::FromRow)]
+pub struct SkillCount {
+    pub day: String,
+    pub skill: String,
+    pub workplace: Option<String>,
+    pub count: i64,
+}
 
-        for job in self {
-            writer
-                .serialize(job)
-                .map_err(|e| Error::Protocol(format!("Failed to write CSV row: {:?}", e).into()))?;
+pub async fn print_database_entries_count(store: &dyn JobStore) {
+    match store.count_jobs().await {
+        Ok(count) => {
+            info!("There is {} entries in database!", count)
+        }
+        Err(e) => {
+            error!("Error printing database count! {}", e)
         }
-
-        writer
-            .flush()
-            .map_err(|e| Error::Protocol(format!("Failed to flush CSV writer: {:?}", e).into()))?;
-        info!("File successfuly saved to {}", file_path.display());
-
-        Ok(())
     }
 }
@@ -0,0 +1,375 @@
+//! Employer activity built from `workplace_name`: the most active employers of a period, their
+//! open ads, reposts and how long their ads stay live, and employers that just appeared.
+use crate::context::AppContext;
+use crate::database::EmployerAd;
+use crate::find_jobs::{OccupationType, Region};
+use crate::municipality::find_municipality;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::io::Write;
+
+pub const DEFAULT_PERIOD_DAYS: i64 = 30;
+pub const DEFAULT_TOP: usize = 20;
+// Earlier ads searched for reposts and first appearances, older ads are not loaded
+const HISTORY_DAYS: i64 = 90;
+const NEW_EMPLOYER_DAYS: i64 = 7;
+
+/// Filters of the report, also the query string of `GET /stats/employers`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct EmployerQuery {
+    /// Length of the period in days, ending now.
+    pub days: i64,
+    pub top: usize,
+    pub region: Option<Region>,
+    pub occupation: Option<OccupationType>,
+}
+
+impl Default for EmployerQuery {
+    fn default() -> Self {
+        Self {
+            days: DEFAULT_PERIOD_DAYS,
+            top: DEFAULT_TOP,
+            region: None,
+            occupation: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct EmployerStats {
+    pub employer: String,
+    /// Ads created in the period.
+    pub new_ads: i64,
+    /// Ads whose last application date has not passed.
+    pub open_ads: i64,
+    /// Ads of the period with the title of an earlier ad from the same employer.
+    pub reposts: i64,
+    /// Mean days between the first and the latest scrape that returned the ads of the period.
+    pub average_days_live: Option<f64>,
+    pub first_seen: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct EmployerReport {
+    pub since: String,
+    /// Employers with the most new ads in the period.
+    pub employers: Vec<EmployerStats>,
+    /// Employers without ads in the 90 days before the last week.
+    pub new_employers: Vec<EmployerStats>,
+}
+
+fn in_scope(query: &EmployerQuery, ad: &EmployerAd) -> bool {
+    let region_match = query.region.as_ref().is_none_or(|region| {
+        ad.workplace
+            .as_deref()
+            .and_then(find_municipality)
+            .is_some_and(|municipality| &municipality.region == region)
+    });
+    let occupation_match = query.occupation.as_ref().is_none_or(|occupation| {
+        ad.occupation
+            .as_deref()
+            .and_then(OccupationType::from_occupation_name)
+            .is_some_and(|ad_occupation| &ad_occupation == occupation)
+    });
+    region_match && occupation_match
+}
+
+fn days_live(ad: &EmployerAd) -> Option<f64> {
+    let created_at = DateTime::parse_from_rfc3339(ad.created_at.as_deref()?).ok()?;
+    let last_seen_at = DateTime::parse_from_rfc3339(ad.last_seen_at.as_deref()?).ok()?;
+    Some((last_seen_at - created_at).num_seconds().max(0) as f64 / 86_400.0)
+}
+
+// Ads are ordered by creation, so the first ad of a title is the original
+fn employer_stats(employer: &str, ads: &[&EmployerAd], since: &str, today: &str) -> EmployerStats {
+    let mut titles = HashSet::new();
+    let mut stats = EmployerStats {
+        employer: employer.to_string(),
+        first_seen: ads
+            .first()
+            .and_then(|ad| ad.created_at.clone())
+            .unwrap_or_default(),
+        ..Default::default()
+    };
+    let mut lifetimes = Vec::new();
+
+    for ad in ads {
+        let title = ad.title.as_deref().unwrap_or_default().to_lowercase();
+        let repost = !titles.insert(title);
+        if ad
+            .created_at
+            .as_deref()
+            .is_some_and(|created| created >= since)
+        {
+            stats.new_ads += 1;
+            stats.reposts += repost as i64;
+            lifetimes.extend(days_live(ad));
+        }
+        if ad
+            .last_application_date
+            .as_deref()
+            .is_some_and(|date| date >= today)
+        {
+            stats.open_ads += 1;
+        }
+    }
+    if !lifetimes.is_empty() {
+        stats.average_days_live = Some(lifetimes.iter().sum::<f64>() / lifetimes.len() as f64);
+    }
+    stats
+}
+
+fn most_active(mut employers: Vec<EmployerStats>, top: usize) -> Vec<EmployerStats> {
+    employers.sort_by(|a, b| {
+        b.new_ads
+            .cmp(&a.new_ads)
+            .then_with(|| a.employer.cmp(&b.employer))
+    });
+    employers.truncate(top);
+    employers
+}
+
+/// Builds the report from the ads of [`JobStore::employer_ads`], oldest first.
+///
+/// [`JobStore::employer_ads`]: crate::store::JobStore::employer_ads
+pub fn build_report(
+    ads: &[EmployerAd],
+    query: &EmployerQuery,
+    now: DateTime<Utc>,
+) -> EmployerReport {
+    let format = |date: DateTime<Utc>| date.format("%Y-%m-%dT%H:%M:%S.%3fZ").to_string();
+    let since = format(now - Duration::days(query.days.max(1)));
+    let new_since = format(now - Duration::days(NEW_EMPLOYER_DAYS));
+    let today = now.format("%Y-%m-%d").to_string();
+
+    let mut by_employer: BTreeMap<&str, Vec<&EmployerAd>> = BTreeMap::new();
+    for ad in ads.iter().filter(|ad| in_scope(query, ad)) {
+        by_employer.entry(&ad.employer).or_default().push(ad);
+    }
+    let employers = by_employer
+        .iter()
+        .map(|(employer, ads)| employer_stats(employer, ads, &since, &today))
+        .collect::<Vec<_>>();
+    let new_employers = employers
+        .iter()
+        .filter(|stats| stats.first_seen >= new_since)
+        .cloned()
+        .collect();
+    let active = employers
+        .into_iter()
+        .filter(|stats| stats.new_ads > 0)
+        .collect();
+
+    EmployerReport {
+        since,
+        employers: most_active(active, query.top),
+        new_employers: most_active(new_employers, query.top),
+    }
+}
+
+pub async fn employer_report(
+    ctx: &AppContext,
+    query: &EmployerQuery,
+) -> Result<EmployerReport, sqlx::Error> {
+    let now = Utc::now();
+    let history = now - Duration::days(query.days.max(1).max(NEW_EMPLOYER_DAYS) + HISTORY_DAYS);
+    let ads = ctx
+        .store
+        .employer_ads(
+            &history.format("%Y-%m-%dT%H:%M:%S.%3fZ").to_string(),
+            &now.format("%Y-%m-%d").to_string(),
+        )
+        .await?;
+    Ok(build_report(&ads, query, now))
+}
+
+fn format_days(days: Option<f64>) -> String {
+    days.map(|days| format!("{:.1}", days))
+        .unwrap_or_else(|| "-".to_string())
+}
+
+impl EmployerReport {
+    fn lists(&self) -> [(&str, &[EmployerStats]); 2] {
+        [
+            ("top", self.employers.as_slice()),
+            ("new", self.new_employers.as_slice()),
+        ]
+    }
+
+    pub fn to_table(&self) -> String {
+        let mut table = String::new();
+        for (heading, employers) in [
+            (
+                format!("Most active employers since {}", self.since),
+                &self.employers,
+            ),
+            ("New employers this week".to_string(), &self.new_employers),
+        ] {
+            table.push_str(&format!("{}\n", heading));
+            table.push_str(&format!(
+                "{:<40}  {:>5}  {:>5}  {:>7}  {:>9}  {}\n",
+                "EMPLOYER", "NEW", "OPEN", "REPOSTS", "DAYS LIVE", "FIRST SEEN"
+            ));
+            for stats in employers {
+                let employer = stats.employer.chars().take(40).collect::<String>();
+                table.push_str(&format!(
+                    "{:<40}  {:>5}  {:>5}  {:>7}  {:>9}  {}\n",
+                    employer,
+                    stats.new_ads,
+                    stats.open_ads,
+                    stats.reposts,
+                    format_days(stats.average_days_live),
+                    stats.first_seen
+                ));
+            }
+            table.push('\n');
+        }
+        table
+    }
+
+    /// Both lists in one table, the `list` column is `top` or `new`.
+    pub fn write_csv<W: Write>(&self, output: W) -> csv::Result<()> {
+        let mut writer = csv::Writer::from_writer(output);
+        writer.write_record([
+            "list",
+            "employer",
+            "new_ads",
+            "open_ads",
+            "reposts",
+            "average_days_live",
+            "first_seen",
+        ])?;
+        for (list, employers) in self.lists() {
+            for stats in employers {
+                writer.write_record([
+                    list.to_string(),
+                    stats.employer.clone(),
+                    stats.new_ads.to_string(),
+                    stats.open_ads.to_string(),
+                    stats.reposts.to_string(),
+                    stats
+                        .average_days_live
+                        .map(|days| format!("{:.1}", days))
+                        .unwrap_or_default(),
+                    stats.first_seen.clone(),
+                ])?;
+            }
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ad(
+        id: &str,
+        employer: &str,
+        title: &str,
+        created_at: &str,
+        last_seen_at: &str,
+    ) -> EmployerAd {
+        EmployerAd {
+            id: id.to_string(),
+            employer: employer.to_string(),
+            title: Some(title.to_string()),
+            workplace: Some("Malmö".to_string()),
+            created_at: Some(created_at.to_string()),
+            last_seen_at: Some(last_seen_at.to_string()),
+            last_application_date: Some("2026-10-01".to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn now() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2026-10-18T12:00:00.000Z")
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn report_counts_reposts_lifetimes_and_new_employers() {
+        let mut open = ad(
+            "4",
+            "Alfa AB",
+            "Lärare",
+            "2026-10-10T08:00:00.000Z",
+            "2026-10-18T08:00:00.000Z",
+        );
+        open.last_application_date = Some("2026-11-01".to_string());
+        let ads = [
+            ad(
+                "1",
+                "Alfa AB",
+                "Lärare",
+                "2026-06-01T08:00:00.000Z",
+                "2026-06-20T08:00:00.000Z",
+            ),
+            ad(
+                "2",
+                "Alfa AB",
+                "Rektor",
+                "2026-10-01T08:00:00.000Z",
+                "2026-10-03T08:00:00.000Z",
+            ),
+            ad(
+                "3",
+                "Beta AB",
+                "Kock",
+                "2026-10-16T08:00:00.000Z",
+                "2026-10-17T08:00:00.000Z",
+            ),
+            open,
+        ];
+        let report = build_report(&ads, &EmployerQuery::default(), now());
+
+        assert_eq!(report.employers.len(), 2);
+        let alfa = &report.employers[0];
+        assert_eq!(alfa.employer, "Alfa AB");
+        assert_eq!(alfa.new_ads, 2);
+        assert_eq!(alfa.reposts, 1);
+        assert_eq!(alfa.open_ads, 1);
+        assert_eq!(alfa.average_days_live, Some(5.0));
+        assert_eq!(alfa.first_seen, "2026-06-01T08:00:00.000Z");
+
+        let new = report
+            .new_employers
+            .iter()
+            .map(|stats| stats.employer.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(new, vec!["Beta AB"]);
+    }
+
+    #[test]
+    fn report_is_filtered_by_region() {
+        let mut elsewhere = ad(
+            "2",
+            "Beta AB",
+            "Kock",
+            "2026-10-16T08:00:00.000Z",
+            "2026-10-16T08:00:00.000Z",
+        );
+        elsewhere.workplace = Some("Kiruna".to_string());
+        let ads = [
+            ad(
+                "1",
+                "Alfa AB",
+                "Lärare",
+                "2026-10-16T08:00:00.000Z",
+                "2026-10-16T08:00:00.000Z",
+            ),
+            elsewhere,
+        ];
+        let query = EmployerQuery {
+            region: Some(Region::Skåne),
+            ..Default::default()
+        };
+        let report = build_report(&ads, &query, now());
+        assert_eq!(report.employers.len(), 1);
+        assert_eq!(report.employers[0].employer, "Alfa AB");
+    }
+}
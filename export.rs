@@ -0,0 +1,41 @@
+//! Exporters for stored job ads.
+use crate::database::DbJobAd;
+use crate::logging::info;
+use csv::Writer;
+use sqlx::Error;
+use std::io::Write;
+use std::path::Path;
+
+/// Writes a collection of ads to a CSV file with one row per ad.
+pub trait SaveToCsv {
+    fn save_to_csv<P: AsRef<Path>>(&self, file_path: P) -> Result<(), Error>;
+}
+
+impl SaveToCsv for Vec<DbJobAd> {
+    fn save_to_csv<P: AsRef<Path>>(&self, file_path: P) -> Result<(), Error> {
+        let file_path = file_path.as_ref();
+        let file = std::fs::File::create(file_path)
+            .map_err(|e| Error::Protocol(format!("Failed to create CSV file: {:?}", e)))?;
+        write_csv(self, file)?;
+        info!("File successfuly saved to {}", file_path.display());
+
+        Ok(())
+    }
+}
+
+/// Writes ads as CSV with a header row to any writer, such as a file or a response body.
+pub fn write_csv<W: Write>(jobs: &[DbJobAd], output: W) -> Result<(), Error> {
+    let mut writer = Writer::from_writer(output);
+
+    for job in jobs {
+        writer
+            .serialize(job)
+            .map_err(|e| Error::Protocol(format!("Failed to write CSV row: {:?}", e)))?;
+    }
+
+    writer
+        .flush()
+        .map_err(|e| Error::Protocol(format!("Failed to flush CSV writer: {:?}", e)))?;
+
+    Ok(())
+}
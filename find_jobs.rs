@@ -1,21 +1,26 @@
-#![allow(unused_variables)]
-#![allow(dead_code)]
-
-use crate::constants::{
-    MAX_RECORDS, PAGE_SIZE, SOURCE_EXTERNAL, SOURCE_PB, URL_JOB_ADS, URL_SEARCH,
-};
-use crate::database::DbJobAd;
+//! Search client for the Platsbanken API.
+
+use crate::constants::{MAX_RECORDS, PAGE_SIZE, SOURCE_EXTERNAL, SOURCE_PB, URL_JOB_ADS};
+use crate::context::AppContext;
+use crate::database::{DbJobAd, ScrapeRun};
+use crate::fixtures::{self, FixtureMode};
+use crate::language::Language;
+use crate::metrics;
+use crate::municipality::Municipality;
+use crate::pipeline::JobSink;
+use crate::salary::Salary;
+use crate::skills;
+use crate::taxonomy::{occupation_fields, occupation_names, taxonomy, ConceptType};
+use crate::work_mode::WorkMode;
 use chrono::{Duration, NaiveDateTime, Utc};
 use log::{error, info};
 use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
-use reqwest::Client;
 use serde::{Deserialize, Serialize};
 
 use serde_json::Value;
+use std::time::Instant;
 use strum::IntoEnumIterator;
-use strum_macros::{EnumIter, EnumString};
-
-use crate::database;
+use strum_macros::{Display, EnumIter, EnumString};
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct JobAd {
@@ -37,6 +42,7 @@ pub struct JobAd {
     pub source_links: Option<Vec<SourceLinks>>,
     #[serde(skip_deserializing)]
     pub created_at: Option<String>,
+    pub description: Option<String>,
 }
 
 impl From<JobAd> for DbJobAd {
@@ -57,10 +63,22 @@ impl From<JobAd> for DbJobAd {
             source: job_ad.source,
             url,
             created_at: job_ad.created_at,
+            description: job_ad.description,
+            ..Default::default()
         }
     }
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct JobDetails {
+    pub id: Option<String>,
+    pub description: Option<String>,
+    #[serde(rename = "salaryType")]
+    pub salary_type: Option<String>,
+    #[serde(rename = "salaryDescription")]
+    pub salary_description: Option<String>,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct SourceLinks {
     pub label: String,
@@ -99,6 +117,8 @@ impl From<JobResponse> for DbJobResponse {
                 source: job_ad.source.clone(),
                 url,
                 created_at: job_ad.created_at.clone(),
+                description: job_ad.description.clone(),
+                ..Default::default()
             };
             db_job_response.ads.push(db_job_ad);
         }
@@ -106,7 +126,8 @@ impl From<JobResponse> for DbJobResponse {
     }
 }
 
-#[derive(PartialEq, Clone)]
+#[derive(PartialEq, Clone, Debug, EnumString)]
+#[strum(serialize_all = "lowercase")]
 pub enum SearchDuration {
     Daily,
     TwoDays,
@@ -178,6 +199,77 @@ impl Region {
             Region::Östergötland => "oLT3_Q9p_3nn",
         }
     }
+
+    pub fn county_code(&self) -> &'static str {
+        match self {
+            Region::Stockholm => "01",
+            Region::Uppsala => "03",
+            Region::Södermanland => "04",
+            Region::Östergötland => "05",
+            Region::Jönköping => "06",
+            Region::Kronoberg => "07",
+            Region::Kalmar => "08",
+            Region::Gotland => "09",
+            Region::Blekinge => "10",
+            Region::Skåne => "12",
+            Region::Halland => "13",
+            Region::VästraGötaland => "14",
+            Region::Värmland => "17",
+            Region::Örebro => "18",
+            Region::Västmanland => "19",
+            Region::Dalarna => "20",
+            Region::Gävleborg => "21",
+            Region::Västernorrland => "22",
+            Region::Jämtland => "23",
+            Region::Västerbotten => "24",
+            Region::Norrbotten => "25",
+        }
+    }
+
+    pub fn from_county_code(code: &str) -> Option<Region> {
+        Region::iter().find(|region| region.county_code() == code)
+    }
+
+    pub fn from_concept_id(id: &str) -> Option<Region> {
+        Region::iter().find(|region| region.get_value() == id)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum SearchFilter {
+    Region(Region),
+    Municipality(Municipality),
+    OccupationField(String),
+    Freetext(String),
+}
+
+impl SearchFilter {
+    pub fn occupation_field(occupation: &OccupationType) -> Option<SearchFilter> {
+        occupation
+            .concept_id()
+            .map(|id| SearchFilter::OccupationField(id.to_string()))
+    }
+
+    pub fn to_json(&self) -> Value {
+        match self {
+            SearchFilter::Region(region) => serde_json::json!({
+                "type": "region",
+                "value": region.get_value()
+            }),
+            SearchFilter::Municipality(municipality) => serde_json::json!({
+                "type": "municipality",
+                "value": municipality.id
+            }),
+            SearchFilter::OccupationField(id) => serde_json::json!({
+                "type": "occupationField",
+                "value": id
+            }),
+            SearchFilter::Freetext(text) => serde_json::json!({
+                "type": "freetext",
+                "value": text
+            }),
+        }
+    }
 }
 
 #[derive(Clone, Debug, EnumString, EnumIter, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -206,6 +298,50 @@ pub enum OccupationType {
 }
 
 impl OccupationType {
+    pub fn taxonomy_label(&self) -> &'static str {
+        match self {
+            OccupationType::AdministrationEkonomiJuridik => "Administration, ekonomi, juridik",
+            OccupationType::ByggAnläggning => "Bygg och anläggning",
+            OccupationType::CheferVerksamhetsledare => "Chefer och verksamhetsledare",
+            OccupationType::DataIT => "Data/IT",
+            OccupationType::FörsäljningInköpMarknadsföring => {
+                "Försäljning, inköp, marknadsföring"
+            }
+            OccupationType::Hantverksyrken => "Hantverksyrken",
+            OccupationType::HotellRestaurangStorhushåll => "Hotell, restaurang, storhushåll",
+            OccupationType::HälsoSjukvård => "Hälso- och sjukvård",
+            OccupationType::IndustriellTillverkning => "Industriell tillverkning",
+            OccupationType::InstallationDriftUnderhåll => "Installation, drift, underhåll",
+            OccupationType::KroppsSkönhetsvård => "Kropps- och skönhetsvård",
+            OccupationType::KulturMediaDesign => "Kultur, media, design",
+            OccupationType::Militär => "Militärt arbete",
+            OccupationType::Naturbruk => "Naturbruk",
+            OccupationType::Naturvetenskapligt => "Naturvetenskapligt arbete",
+            OccupationType::Pedagogiskt => "Pedagogiskt arbete",
+            OccupationType::SaneringRenhållning => "Sanering och renhållning",
+            OccupationType::Socialt => "Socialt arbete",
+            OccupationType::Säkerhet => "Säkerhetsarbete",
+            OccupationType::Teknisk => "Tekniskt arbete",
+            OccupationType::Transport => "Transport",
+        }
+    }
+
+    // Concept id of the occupation field, resolved through the taxonomy snapshot
+    pub fn concept_id(&self) -> Option<&'static str> {
+        taxonomy()
+            .by_label(ConceptType::OccupationField, self.taxonomy_label())
+            .map(|concept| concept.id.as_str())
+    }
+
+    pub fn from_concept_id(id: &str) -> Option<OccupationType> {
+        occupation_fields().get(id).cloned()
+    }
+
+    // Resolves the occupation field of an ad from its occupation name
+    pub fn from_occupation_name(name: &str) -> Option<OccupationType> {
+        occupation_names().get(&name.trim().to_lowercase()).cloned()
+    }
+
     pub fn as_readable_string(&self) -> String {
         let name = format!("{:?}", self);
         if name.starts_with("Data") {
@@ -221,6 +357,35 @@ impl OccupationType {
     }
 }
 
+async fn search(
+    ctx: &AppContext,
+    headers: &HeaderMap,
+    payload: &Value,
+) -> Result<JobResponse, Box<dyn std::error::Error>> {
+    let body = match &ctx.config.fixture_mode {
+        FixtureMode::Replay(dir) => fixtures::replay(dir, payload)?,
+        mode => {
+            let response = ctx
+                .client
+                .post(ctx.search_url())
+                .headers(headers.clone())
+                .json(payload)
+                .send()
+                .await
+                .inspect_err(|_| metrics::SEARCH_REQUESTS.with_label_values(&["error"]).inc())?;
+            metrics::SEARCH_REQUESTS
+                .with_label_values(&[response.status().as_str()])
+                .inc();
+            let body = response.error_for_status()?.text().await?;
+            if let FixtureMode::Record(dir) = mode {
+                fixtures::record(dir, payload, &body)?;
+            }
+            body
+        }
+    };
+    Ok(serde_json::from_str(&body)?)
+}
+
 fn setup_headers() -> HeaderMap {
     let mut headers = HeaderMap::new();
     headers.insert(
@@ -240,602 +405,766 @@ fn setup_headers() -> HeaderMap {
     headers
 }
 
-pub async fn get_jobs_pb(duration: &SearchDuration) -> Result<(), Box<dyn std::error::Error>> {
-    // Searches all the variants of the Enum Region to make it possible to fetch all jobs
-    // Main function to retrieve all jobs
-    // Platsbanken
-    let client = Client::new();
+/// Outcome of a scrape, from best to worst so the worst of several reports is their maximum.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Display)]
+#[strum(serialize_all = "lowercase")]
+pub enum ScrapeStatus {
+    Success,
+    /// Failed after some ads had been stored.
+    Partial,
+    Failed,
+}
 
-    let headers = setup_headers();
-    let from_date: Value;
-    if duration.to_days() != 0 {
-        let date = Utc::now() - Duration::days(duration.to_days());
-        from_date = Value::String(date.format("%Y-%m-%dT%H:%M:%S.%3fZ").to_string());
-    } else {
-        from_date = Value::Null;
-    }
-    let to_date = chrono::Utc::now()
-        .format("%Y-%m-%dT%H:%M:%S.%3fZ")
-        .to_string();
-
-    let mut vec_res: Vec<JobAd> = Vec::new();
-    for region in Region::iter() {
-        let mut start_records = 0;
-        while start_records < MAX_RECORDS {
-            let payload = serde_json::json!({
-                "filters": [
-                    {
-                        "type": "region",
-                        "value": region.get_value()
-                    }],
-                "fromDate": from_date,
-                "order": "date",
-                "maxRecords": PAGE_SIZE,
-                "startIndex": start_records,
-                "toDate": to_date,
-                "source": SOURCE_PB.to_string(),
-            });
-            let mut res: JobResponse = client
-                .post(URL_SEARCH)
-                .headers(headers.clone())
-                .json(&payload)
-                .send()
-                .await?
-                .json()
-                .await?;
+/// Result of scraping one source, built from its [`ScrapeRun`].
+#[derive(Clone, Debug)]
+pub struct ScrapeReport {
+    pub source: String,
+    pub status: ScrapeStatus,
+    pub pages_fetched: i64,
+    pub ads_received: i64,
+    pub ads_available: i64,
+    pub ads_inserted: i64,
+    pub ads_updated: i64,
+    pub error: Option<String>,
+}
 
-            if res.ads.is_empty() {
-                break;
-            }
-            for job in res.ads.drain(..) {
-                let mut job = job;
-                job.published_date = job.published_date.as_ref().map(|date| {
-                    NaiveDateTime::parse_from_str(date, "%Y-%m-%dT%H:%M:%S%.3fZ")
-                        .map(|dt| dt.date().to_string())
-                        .unwrap_or_else(|_| "Invalid date".to_string())
-                });
-                job.last_application_date = job.last_application_date.as_ref().map(|date| {
-                    NaiveDateTime::parse_from_str(date, "%Y-%m-%dT%H:%M:%S%.3fZ")
-                        .map(|dt| dt.date().to_string())
-                        .unwrap_or_else(|_| "Invalid date".to_string())
-                });
-
-                job.source = Some("Platsbanken".to_string());
-                job.url = match &job.id {
-                    Some(id) => Some(format!("{}{}", URL_JOB_ADS, id)),
-                    None => None,
-                };
-
-                job.created_at = Some(Utc::now().format("%Y-%m-%dT%H:%M:%S.%3fZ").to_string());
-                vec_res.push(job);
-            }
-            start_records += PAGE_SIZE;
+impl ScrapeReport {
+    /// A source that failed before anything could be fetched or recorded.
+    pub fn failed(source: &str, error: String) -> Self {
+        Self {
+            source: source.to_string(),
+            status: ScrapeStatus::Failed,
+            pages_fetched: 0,
+            ads_received: 0,
+            ads_available: 0,
+            ads_inserted: 0,
+            ads_updated: 0,
+            error: Some(error),
         }
     }
-
-    database::insert_jobs(vec_res).await?;
-    Ok(())
 }
 
-pub async fn get_jobs_external(
-    duration: &SearchDuration,
-) -> Result<(), Box<dyn std::error::Error>> {
-    // Searches all the variants of the Enum Region to make it possible to fetch all jobs
-    // Main function to retrieve all jobs
-    // External
-    let client = Client::new();
-
-    let headers = setup_headers();
-    let from_date: Value;
-    if duration.to_days() != 0 {
-        let date = Utc::now() - Duration::days(duration.to_days());
-        from_date = Value::String(date.format("%Y-%m-%dT%H:%M:%S.%3fZ").to_string());
-    } else {
-        from_date = Value::Null;
-    }
-    let to_date = chrono::Utc::now()
-        .format("%Y-%m-%dT%H:%M:%S.%3fZ")
-        .to_string();
-
-    let mut vec_res: Vec<JobAd> = Vec::new();
-    for region in Region::iter() {
-        let mut start_records = 0;
-        while start_records < MAX_RECORDS {
-            let payload = serde_json::json!({
-                "filters": [
-                    {
-                        "type": "region",
-                        "value": region.get_value()
-                    }],
-                "fromDate": from_date,
-                "order": "date",
-                "maxRecords": PAGE_SIZE,
-                "startIndex": start_records,
-                "toDate": to_date,
-                "source": SOURCE_EXTERNAL.to_string(),
-            });
-            let mut res: JobResponse = client
-                .post(URL_SEARCH)
-                .headers(headers.clone())
-                .json(&payload)
-                .send()
-                .await?
-                .json()
-                .await?;
-
-            if res.ads.is_empty() {
-                break;
-            }
-            for job in res.ads.drain(..) {
-                let mut job = job;
-                job.published_date = job.published_date.as_ref().map(|date| {
-                    NaiveDateTime::parse_from_str(date, "%Y-%m-%dT%H:%M:%S%.3fZ")
-                        .map(|dt| dt.date().to_string())
-                        .unwrap_or_else(|_| "Invalid date".to_string())
-                });
-                job.last_application_date = job.last_application_date.as_ref().map(|date| {
-                    NaiveDateTime::parse_from_str(date, "%Y-%m-%dT%H:%M:%S%.3fZ")
-                        .map(|dt| dt.date().to_string())
-                        .unwrap_or_else(|_| "Invalid date".to_string())
-                });
-
-                job.source_links
-                    .as_ref()
-                    .and_then(|links| links.first())
-                    .map(|link| link.url.clone());
-
-                job.created_at = Some(Utc::now().format("%Y-%m-%dT%H:%M:%S.%3fZ").to_string());
-                vec_res.push(job);
-            }
-            start_records += PAGE_SIZE;
+impl From<&ScrapeRun> for ScrapeReport {
+    fn from(run: &ScrapeRun) -> Self {
+        let status = match &run.error {
+            None => ScrapeStatus::Success,
+            Some(_) if run.ads_inserted + run.ads_updated > 0 => ScrapeStatus::Partial,
+            Some(_) => ScrapeStatus::Failed,
+        };
+        Self {
+            source: run.source.clone(),
+            status,
+            pages_fetched: run.pages_fetched,
+            ads_received: run.ads_received,
+            ads_available: run.ads_available,
+            ads_inserted: run.ads_inserted,
+            ads_updated: run.ads_updated,
+            error: run.error.clone(),
         }
     }
-
-    database::insert_jobs(vec_res).await?;
-    Ok(())
 }
 
-pub async fn get_jobs_with_title(
-    duration: &SearchDuration,
-    title: &str,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let client = Client::new();
-    let headers = setup_headers();
-    let from_date = serde_json::Value::Null;
-    if duration.to_days() != 0 {
-        let from_date = Utc::now() - Duration::days(duration.to_days());
-        let from_date = from_date.format("%Y-%m-%dT%H:%M:%S.%3fZ").to_string();
-    }
-    let to_date = chrono::Utc::now()
-        .format("%Y-%m-%dT%H:%M:%S.%3fZ")
-        .to_string();
-
-    let mut vec_res: Vec<JobAd> = Vec::new();
-    let job_sources = vec![SOURCE_PB.to_string(), SOURCE_EXTERNAL.to_string()];
-    for source in job_sources.iter() {
-        let mut start_records = 0;
-        while start_records < MAX_RECORDS {
-            let payload = serde_json::json!({
-                "filters": [{
-                    "type": "freetext",
-                    "value": title.to_string()}],
-                "fromDate": from_date,
-                "order": "date",
-                "maxRecords": PAGE_SIZE,
-                "startIndex": start_records,
-                "toDate": to_date,
-                "source": source,
-            });
-            let mut res: JobResponse = client
-                .post(URL_SEARCH)
-                .headers(headers.clone())
-                .json(&payload)
-                .send()
-                .await?
-                .json()
-                .await?;
-
-            for job in res.ads.drain(..) {
-                let mut job = job;
-                job.published_date = job.published_date.as_ref().map(|date| {
-                    NaiveDateTime::parse_from_str(date, "%Y-%m-%dT%H:%M:%S%.3fZ")
-                        .map(|dt| dt.date().to_string())
-                        .unwrap_or_else(|_| "Invalid date".to_string())
-                });
-
-                job.last_application_date = job.last_application_date.as_ref().map(|date| {
-                    NaiveDateTime::parse_from_str(date, "%Y-%m-%dT%H:%M:%S%.3fZ")
-                        .map(|dt| dt.date().to_string())
-                        .unwrap_or_else(|_| "Invalid date".to_string())
-                });
-
-                job.source = if source == &SOURCE_PB.to_string() {
-                    Some("Platsbanken".to_string())
-                } else {
-                    Some("External".to_string())
-                };
-                job.url = if source == &SOURCE_PB.to_string() {
-                    match &job.id {
-                        Some(id) => Some(format!("{}{}", URL_JOB_ADS, id)),
-                        None => None,
-                    }
-                } else {
-                    job.source_links
-                        .as_ref()
-                        .and_then(|links| links.first())
-                        .map(|link| link.url.clone())
-                };
-
-                job.created_at = Some(Utc::now().format("%Y-%m-%dT%H:%M:%S.%3fZ").to_string());
-                vec_res.push(job);
-            }
-            start_records += PAGE_SIZE;
+impl std::fmt::Display for ScrapeReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: {}, {} pages, {} of {} ads received, {} inserted, {} updated",
+            self.source,
+            self.status,
+            self.pages_fetched,
+            self.ads_received,
+            self.ads_available,
+            self.ads_inserted,
+            self.ads_updated
+        )?;
+        if let Some(error) = &self.error {
+            write!(f, " ({})", error)?;
         }
+        Ok(())
     }
-    database::insert_jobs(vec_res).await?;
-    Ok(())
 }
 
-pub async fn get_jobs_with_search_region(
-    duration: &SearchDuration,
-    region: Region,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let client = Client::new();
+fn date_range(duration: &SearchDuration) -> (Value, String) {
+    let from_date = if duration.to_days() != 0 {
+        let date = Utc::now() - Duration::days(duration.to_days());
+        Value::String(date.format("%Y-%m-%dT%H:%M:%S.%3fZ").to_string())
+    } else {
+        Value::Null
+    };
+    let to_date = Utc::now().format("%Y-%m-%dT%H:%M:%S.%3fZ").to_string();
+    (from_date, to_date)
+}
 
-    let headers = setup_headers();
-    let from_date = serde_json::Value::Null;
-    if duration.to_days() != 0 {
-        let from_date = Utc::now() - Duration::days(duration.to_days());
-        let from_date = from_date.format("%Y-%m-%dT%H:%M:%S.%3fZ").to_string();
-    }
-    let to_date = chrono::Utc::now()
-        .format("%Y-%m-%dT%H:%M:%S.%3fZ")
-        .to_string();
-
-    let mut vec_res: Vec<JobAd> = Vec::new();
-    let job_sources = vec![SOURCE_PB.to_string(), SOURCE_EXTERNAL.to_string()];
-    for source in job_sources.iter() {
-        let mut start_records = 0;
-        while start_records < MAX_RECORDS {
-            let payload = serde_json::json!({
-                "filters": [{
-                    "type": "region",
-                    "value": region.get_value()}],
-                "fromDate": from_date,
-                "order": "date",
-                "maxRecords": PAGE_SIZE,
-                "startIndex": start_records,
-                "toDate": to_date,
-                "source": source,
-            });
-            let mut res: JobResponse = client
-                .post(URL_SEARCH)
-                .headers(headers.clone())
-                .json(&payload)
-                .send()
-                .await?
-                .json()
-                .await?;
-
-            for job in res.ads.drain(..) {
-                let mut job = job;
-                job.published_date = job.published_date.as_ref().map(|date| {
-                    NaiveDateTime::parse_from_str(date, "%Y-%m-%dT%H:%M:%S%.3fZ")
-                        .map(|dt| dt.date().to_string())
-                        .unwrap_or_else(|_| "Invalid date".to_string())
-                });
-
-                job.last_application_date = job.last_application_date.as_ref().map(|date| {
-                    NaiveDateTime::parse_from_str(date, "%Y-%m-%dT%H:%M:%S%.3fZ")
-                        .map(|dt| dt.date().to_string())
-                        .unwrap_or_else(|_| "Invalid date".to_string())
-                });
-
-                job.source = if source == &SOURCE_PB.to_string() {
-                    Some("Platsbanken".to_string())
-                } else {
-                    Some("External".to_string())
-                };
-                job.url = if source == &SOURCE_PB.to_string() {
-                    match &job.id {
-                        Some(id) => Some(format!("{}{}", URL_JOB_ADS, id)),
-                        None => None,
-                    }
-                } else {
-                    job.source_links
-                        .as_ref()
-                        .and_then(|links| links.first())
-                        .map(|link| link.url.clone())
-                };
-
-                job.created_at = Some(Utc::now().format("%Y-%m-%dT%H:%M:%S.%3fZ").to_string());
-
-                vec_res.push(job);
-            }
-            start_records += PAGE_SIZE;
-        }
+fn normalize_job(mut job: JobAd, source: &str) -> JobAd {
+    job.published_date = job.published_date.as_ref().map(|date| {
+        NaiveDateTime::parse_from_str(date, "%Y-%m-%dT%H:%M:%S%.3fZ")
+            .map(|dt| dt.date().to_string())
+            .unwrap_or_else(|_| "Invalid date".to_string())
+    });
+    job.last_application_date = job.last_application_date.as_ref().map(|date| {
+        NaiveDateTime::parse_from_str(date, "%Y-%m-%dT%H:%M:%S%.3fZ")
+            .map(|dt| dt.date().to_string())
+            .unwrap_or_else(|_| "Invalid date".to_string())
+    });
+
+    if source == SOURCE_PB {
+        job.source = Some("Platsbanken".to_string());
+        job.url = job.id.as_ref().map(|id| format!("{}{}", URL_JOB_ADS, id));
+    } else {
+        job.source = Some("External".to_string());
+        job.url = job
+            .source_links
+            .as_ref()
+            .and_then(|links| links.first())
+            .map(|link| link.url.clone());
     }
-    database::insert_jobs(vec_res).await?;
-    Ok(())
+
+    job.created_at = Some(Utc::now().format("%Y-%m-%dT%H:%M:%S.%3fZ").to_string());
+    job
 }
 
-pub async fn get_jobs_with_search_region_and_title(
-    duration: &SearchDuration,
-    region: Region,
-    title: &str,
+// Pages through one search until an empty page and hands every page to the sink
+async fn fetch_search(
+    ctx: &AppContext,
+    headers: &HeaderMap,
+    sink: &JobSink,
+    run: &mut ScrapeRun,
+    source: &str,
+    mut payload: Value,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let client = Client::new();
+    let mut start_records = 0;
+    while start_records < MAX_RECORDS {
+        payload["startIndex"] = Value::from(start_records);
+        let res = search(ctx, headers, &payload).await?;
+        run.pages_fetched += 1;
+        metrics::PAGES_FETCHED
+            .with_label_values(&[run.source.as_str()])
+            .inc();
+        if start_records == 0 {
+            run.ads_available += res.number_of_ads;
+        }
 
-    let headers = setup_headers();
-    let from_date = serde_json::Value::Null;
-    if duration.to_days() != 0 {
-        let from_date = Utc::now() - Duration::days(duration.to_days());
-        let from_date = from_date.format("%Y-%m-%dT%H:%M:%S.%3fZ").to_string();
-    }
-    let to_date = chrono::Utc::now()
-        .format("%Y-%m-%dT%H:%M:%S.%3fZ")
-        .to_string();
-
-    let mut vec_res: Vec<JobAd> = Vec::new();
-    let job_sources = vec![SOURCE_PB.to_string(), SOURCE_EXTERNAL.to_string()];
-    for source in job_sources.iter() {
-        let mut start_records = 0;
-        while start_records < MAX_RECORDS {
-            let payload = serde_json::json!({
-                "filters": [
-                    {
-                        "type": "freetext",
-                        "value": title.to_string(),
-                    },
-                    {
-                        "type": "region",
-                        "value": region.get_value()
-                    }],
-                "fromDate": from_date,
-                "order": "date",
-                "maxRecords": PAGE_SIZE,
-                "startIndex": start_records,
-                "toDate": to_date,
-                "source": source,
-            });
-            let mut res: JobResponse = client
-                .post(URL_SEARCH)
-                .headers(headers.clone())
-                .json(&payload)
-                .send()
-                .await?
-                .json()
-                .await?;
-
-            for job in res.ads.drain(..) {
-                let mut job = job;
-                job.published_date = job.published_date.as_ref().map(|date| {
-                    NaiveDateTime::parse_from_str(date, "%Y-%m-%dT%H:%M:%S%.3fZ")
-                        .map(|dt| dt.date().to_string())
-                        .unwrap_or_else(|_| "Invalid date".to_string())
-                });
-
-                job.last_application_date = job.last_application_date.as_ref().map(|date| {
-                    NaiveDateTime::parse_from_str(date, "%Y-%m-%dT%H:%M:%S%.3fZ")
-                        .map(|dt| dt.date().to_string())
-                        .unwrap_or_else(|_| "Invalid date".to_string())
-                });
-
-                job.source = if source == &SOURCE_PB.to_string() {
-                    Some("Platsbanken".to_string())
-                } else {
-                    Some("External".to_string())
-                };
-                job.url = if source == &SOURCE_PB.to_string() {
-                    match &job.id {
-                        Some(id) => Some(format!("{}{}", URL_JOB_ADS, id)),
-                        None => None,
-                    }
-                } else {
-                    job.source_links
-                        .as_ref()
-                        .and_then(|links| links.first())
-                        .map(|link| link.url.clone())
-                };
-
-                job.created_at = Some(Utc::now().format("%Y-%m-%dT%H:%M:%S.%3fZ").to_string());
-                vec_res.push(job);
-            }
-            start_records += PAGE_SIZE;
+        if res.ads.is_empty() {
+            break;
         }
+        run.ads_received += res.ads.len() as i64;
+        let jobs = res
+            .ads
+            .into_iter()
+            .map(|job| normalize_job(job, source))
+            .collect();
+        sink.send(jobs).await?;
+        start_records += PAGE_SIZE;
     }
-    database::insert_jobs(vec_res).await?;
     Ok(())
 }
 
-pub async fn get_jobs_abroad(duration: &SearchDuration) -> Result<(), Box<dyn std::error::Error>> {
-    let client = Client::new();
-
+// Runs the searches one after another while a JobSink stores the ads as they arrive, so the
+// pages fetched before a failure are kept. The run is recorded in scrape_runs under `name`,
+// fetch errors end up in the report and only failing to record the run is an Err.
+async fn stream_searches(
+    ctx: &AppContext,
+    name: &str,
+    duration: &SearchDuration,
+    searches: Vec<(&str, Value)>,
+) -> Result<ScrapeReport, Box<dyn std::error::Error>> {
+    let started = Instant::now();
     let headers = setup_headers();
-    let from_date: Value;
-    if duration.to_days() != 0 {
-        let date = Utc::now() - Duration::days(duration.to_days());
-        from_date = Value::String(date.format("%Y-%m-%dT%H:%M:%S.%3fZ").to_string());
-    } else {
-        from_date = Value::Null;
-    }
-    let to_date = chrono::Utc::now()
-        .format("%Y-%m-%dT%H:%M:%S.%3fZ")
-        .to_string();
-
-    let mut vec_res: Vec<JobAd> = Vec::new();
-    let mut start_records = 0;
-    while start_records < MAX_RECORDS {
+    let (from_date, to_date) = date_range(duration);
+    let mut filters = searches
+        .iter()
+        .map(|(_, filters)| filters)
+        .collect::<Vec<_>>();
+    filters.dedup();
+
+    let mut run = ScrapeRun {
+        started_at: Utc::now().format("%Y-%m-%dT%H:%M:%S.%3fZ").to_string(),
+        source: name.to_string(),
+        filters: serde_json::to_string(&filters).ok(),
+        ..Default::default()
+    };
+    run.id = ctx.store.start_scrape_run(&run).await?;
+    let sink = JobSink::spawn(ctx);
+
+    let mut error = None;
+    for (source, filters) in searches.iter() {
         let payload = serde_json::json!({
-            "filters": [
-                {
-                    "type": "abroad",
-                    "value": "true"
-                }],
+            "filters": filters,
             "fromDate": from_date,
             "order": "date",
             "maxRecords": PAGE_SIZE,
-            "startIndex": start_records,
+            "startIndex": 0,
             "toDate": to_date,
-            "source": SOURCE_PB.to_string(),
+            "source": source,
         });
-        let mut res: JobResponse = client
-            .post(URL_SEARCH)
-            .headers(headers.clone())
-            .json(&payload)
-            .send()
-            .await?
-            .json()
-            .await?;
-
-        if res.ads.is_empty() {
+        // The error is not Send, keep only the message while waiting for the writer
+        if let Err(e) = fetch_search(ctx, &headers, &sink, &mut run, source, payload).await {
+            error = Some(e.to_string());
             break;
         }
-        for job in res.ads.drain(..) {
-            let mut job = job;
-            job.published_date = job.published_date.as_ref().map(|date| {
-                NaiveDateTime::parse_from_str(date, "%Y-%m-%dT%H:%M:%S%.3fZ")
-                    .map(|dt| dt.date().to_string())
-                    .unwrap_or_else(|_| "Invalid date".to_string())
-            });
-            job.last_application_date = job.last_application_date.as_ref().map(|date| {
-                NaiveDateTime::parse_from_str(date, "%Y-%m-%dT%H:%M:%S%.3fZ")
-                    .map(|dt| dt.date().to_string())
-                    .unwrap_or_else(|_| "Invalid date".to_string())
-            });
-
-            job.source = Some("Platsbanken".to_string());
-            job.url = match &job.id {
-                Some(id) => Some(format!("{}{}", URL_JOB_ADS, id)),
-                None => None,
-            };
+    }
 
-            job.created_at = Some(Utc::now().format("%Y-%m-%dT%H:%M:%S.%3fZ").to_string());
-            vec_res.push(job);
+    match sink.finish().await {
+        Ok(stats) => {
+            run.ads_inserted = stats.inserted as i64;
+            run.ads_updated = stats.updated as i64;
+            metrics::ADS_WRITTEN
+                .with_label_values(&[name, "inserted"])
+                .inc_by(stats.inserted);
+            metrics::ADS_WRITTEN
+                .with_label_values(&[name, "updated"])
+                .inc_by(stats.updated);
+            metrics::ADS_WRITTEN
+                .with_label_values(&[name, "dropped"])
+                .inc_by(
+                    stats
+                        .received
+                        .saturating_sub(stats.inserted + stats.updated),
+                );
+            info!(
+                "Inserted {} and updated {} of {} entries in {} batches, {} ms spent writing",
+                stats.inserted,
+                stats.updated,
+                stats.received,
+                stats.batches,
+                stats.write_time.as_millis()
+            );
         }
-        start_records += PAGE_SIZE;
+        // A stopped writer also fails the fetching, its error is the one worth keeping
+        Err(e) => error = Some(e.to_string()),
     }
-    database::insert_jobs(vec_res).await?;
-    Ok(())
+    run.error = error;
+    run.finished_at = Some(Utc::now().format("%Y-%m-%dT%H:%M:%S.%3fZ").to_string());
+    ctx.store.finish_scrape_run(&run).await?;
+    metrics::SCRAPE_DURATION
+        .with_label_values(&[name])
+        .observe(started.elapsed().as_secs_f64());
+    Ok(ScrapeReport::from(&run))
 }
 
-pub async fn get_jobs_unspecified(
+fn both_sources(filters: Value) -> Vec<(&'static str, Value)> {
+    vec![(SOURCE_PB, filters.clone()), (SOURCE_EXTERNAL, filters)]
+}
+
+pub async fn get_jobs_pb(
+    ctx: &AppContext,
     duration: &SearchDuration,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let client = Client::new();
+) -> Result<ScrapeReport, Box<dyn std::error::Error>> {
+    // Searches all the variants of the Enum Region to make it possible to fetch all jobs
+    // Main function to retrieve all jobs
+    // Platsbanken
+    let searches = Region::iter()
+        .map(|region| {
+            (
+                SOURCE_PB,
+                serde_json::json!([SearchFilter::Region(region).to_json()]),
+            )
+        })
+        .collect();
+    stream_searches(ctx, "pb", duration, searches).await
+}
 
-    let headers = setup_headers();
-    let from_date: Value;
-    if duration.to_days() != 0 {
-        let date = Utc::now() - Duration::days(duration.to_days());
-        from_date = Value::String(date.format("%Y-%m-%dT%H:%M:%S.%3fZ").to_string());
-    } else {
-        from_date = Value::Null;
-    }
-    let to_date = chrono::Utc::now()
-        .format("%Y-%m-%dT%H:%M:%S.%3fZ")
-        .to_string();
+pub async fn get_jobs_external(
+    ctx: &AppContext,
+    duration: &SearchDuration,
+) -> Result<ScrapeReport, Box<dyn std::error::Error>> {
+    // Searches all the variants of the Enum Region to make it possible to fetch all jobs
+    // Main function to retrieve all jobs
+    // External
+    let searches = Region::iter()
+        .map(|region| {
+            (
+                SOURCE_EXTERNAL,
+                serde_json::json!([SearchFilter::Region(region).to_json()]),
+            )
+        })
+        .collect();
+    stream_searches(ctx, "external", duration, searches).await
+}
 
-    let mut vec_res: Vec<JobAd> = Vec::new();
-    let mut start_records = 0;
-    while start_records < MAX_RECORDS {
-        let payload = serde_json::json!({
-            "filters": [
-                {
-                    "type": "unspecifiedSwedenWorkplace",
-                    "value": "true"
-                }],
-            "fromDate": from_date,
-            "order": "date",
-            "maxRecords": PAGE_SIZE,
-            "startIndex": start_records,
-            "toDate": to_date,
-            "source": SOURCE_PB.to_string(),
-        });
-        let mut res: JobResponse = client
-            .post(URL_SEARCH)
-            .headers(headers.clone())
-            .json(&payload)
-            .send()
-            .await?
-            .json()
-            .await?;
+pub async fn get_jobs_with_title(
+    ctx: &AppContext,
+    duration: &SearchDuration,
+    title: &str,
+) -> Result<ScrapeReport, Box<dyn std::error::Error>> {
+    let filters = serde_json::json!([SearchFilter::Freetext(title.to_string()).to_json()]);
+    stream_searches(ctx, "title", duration, both_sources(filters)).await
+}
 
-        if res.ads.is_empty() {
-            break;
-        }
-        for job in res.ads.drain(..) {
-            let mut job = job;
-            job.published_date = job.published_date.as_ref().map(|date| {
-                NaiveDateTime::parse_from_str(date, "%Y-%m-%dT%H:%M:%S%.3fZ")
-                    .map(|dt| dt.date().to_string())
-                    .unwrap_or_else(|_| "Invalid date".to_string())
-            });
-            job.last_application_date = job.last_application_date.as_ref().map(|date| {
-                NaiveDateTime::parse_from_str(date, "%Y-%m-%dT%H:%M:%S%.3fZ")
-                    .map(|dt| dt.date().to_string())
-                    .unwrap_or_else(|_| "Invalid date".to_string())
-            });
-
-            job.source = Some("Platsbanken".to_string());
-            job.url = match &job.id {
-                Some(id) => Some(format!("{}{}", URL_JOB_ADS, id)),
-                None => None,
-            };
+pub async fn get_jobs_with_search_region(
+    ctx: &AppContext,
+    duration: &SearchDuration,
+    region: Region,
+) -> Result<ScrapeReport, Box<dyn std::error::Error>> {
+    let filters = serde_json::json!([SearchFilter::Region(region).to_json()]);
+    stream_searches(ctx, "region", duration, both_sources(filters)).await
+}
+
+pub async fn get_jobs_with_search_region_and_title(
+    ctx: &AppContext,
+    duration: &SearchDuration,
+    region: Region,
+    title: &str,
+) -> Result<ScrapeReport, Box<dyn std::error::Error>> {
+    let filters = serde_json::json!([
+        SearchFilter::Freetext(title.to_string()).to_json(),
+        SearchFilter::Region(region).to_json(),
+    ]);
+    stream_searches(ctx, "region_title", duration, both_sources(filters)).await
+}
+
+pub async fn get_jobs_with_filters(
+    ctx: &AppContext,
+    duration: &SearchDuration,
+    filters: &[SearchFilter],
+) -> Result<ScrapeReport, Box<dyn std::error::Error>> {
+    let filters = Value::Array(filters.iter().map(SearchFilter::to_json).collect());
+    stream_searches(ctx, "filters", duration, both_sources(filters)).await
+}
+
+pub async fn get_jobs_abroad(
+    ctx: &AppContext,
+    duration: &SearchDuration,
+) -> Result<ScrapeReport, Box<dyn std::error::Error>> {
+    let filters = serde_json::json!([
+    {
+        "type": "abroad",
+        "value": "true"
+    }]);
+    stream_searches(ctx, "abroad", duration, vec![(SOURCE_PB, filters)]).await
+}
+
+pub async fn get_jobs_unspecified(
+    ctx: &AppContext,
+    duration: &SearchDuration,
+) -> Result<ScrapeReport, Box<dyn std::error::Error>> {
+    let filters = serde_json::json!([
+    {
+        "type": "unspecifiedSwedenWorkplace",
+        "value": "true"
+    }]);
+    stream_searches(ctx, "unspecified", duration, vec![(SOURCE_PB, filters)]).await
+}
+
+pub async fn get_job_details(
+    ctx: &AppContext,
+    id: &str,
+) -> Result<JobDetails, Box<dyn std::error::Error>> {
+    let mut details: JobDetails = ctx
+        .client
+        .get(ctx.job_details_url(id))
+        .headers(setup_headers())
+        .send()
+        .await?
+        .json()
+        .await?;
+
+    details.description = details
+        .description
+        .map(|description| strip_html(&description));
+    Ok(details)
+}
 
-            job.created_at = Some(Utc::now().format("%Y-%m-%dT%H:%M:%S.%3fZ").to_string());
-            vec_res.push(job);
+/// Fetches the description and salary of ads whose details have not been fetched. Ads whose
+/// details fail are tried again on later runs, up to [`crate::constants::MAX_DETAILS_ATTEMPTS`]
+/// times.
+pub async fn fetch_missing_details(
+    ctx: &AppContext,
+    limit: i64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Descriptions and salaries are not part of the search response and need one request per ad
+    let ids = ctx.store.job_ids_without_details(limit).await?;
+
+    let mut fetched = 0;
+    for id in ids.iter() {
+        match get_job_details(ctx, id).await {
+            Ok(details) => {
+                if let Some(description) = &details.description {
+                    ctx.store.update_description(id, description).await?;
+                    let skills = skills::dictionary().job_skills(id, None, Some(description));
+                    ctx.store.add_job_skills(&skills).await?;
+                    // The title was classified with the ad, the description needs it again
+                    let job = ctx.store.get_job(id).await?;
+                    let title = job.as_ref().and_then(|job| job.title.as_deref());
+                    let workplace = job.as_ref().and_then(|job| job.workplace.as_deref());
+                    let language = Language::detect(title, Some(description));
+                    ctx.store.update_language(id, language).await?;
+                    let work_mode = WorkMode::classify(workplace, title, Some(description));
+                    ctx.store.update_work_mode(id, Some(work_mode)).await?;
+                }
+                let salary = Salary::parse(
+                    details.salary_type.as_deref(),
+                    details.salary_description.as_deref(),
+                );
+                ctx.store.update_salary(id, &salary).await?;
+                let fetched_at = Utc::now().format("%Y-%m-%dT%H:%M:%S.%3fZ").to_string();
+                ctx.store.mark_details_fetched(id, &fetched_at).await?;
+                fetched += 1;
+            }
+            Err(e) => {
+                error!("Failed to fetch details for {}: {:?}", id, e);
+                ctx.store.record_details_failure(id).await?;
+            }
         }
-        start_records += PAGE_SIZE;
     }
-    database::insert_jobs(vec_res).await?;
+
+    info!("Fetched details for {} of {} ads", fetched, ids.len());
     Ok(())
 }
 
-pub async fn get_all_jobs(search_duration: &SearchDuration) -> Result<(), Box<dyn std::error::Error>> {
+fn strip_html(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => {
+                in_tag = true;
+                text.push(' ');
+            }
+            '>' => in_tag = false,
+            _ if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Scrapes Platsbanken, external ads, ads abroad and ads without a workplace concurrently and
+/// returns one report per source. A failing source does not stop the others.
+pub async fn get_all_jobs(ctx: &AppContext, search_duration: &SearchDuration) -> Vec<ScrapeReport> {
     let pb_duration = search_duration.clone();
     let external_duration = search_duration.clone();
     let abroad_duration = search_duration.clone();
     let unspecified_duration = search_duration.clone();
+    let (pb_ctx, external_ctx, abroad_ctx, unspecified_ctx) =
+        (ctx.clone(), ctx.clone(), ctx.clone(), ctx.clone());
 
     let pb_handle = tokio::spawn(async move {
-        if let Err(e) = get_jobs_pb(&pb_duration).await {
-            error!("Failed to get jobs from PB: {:?}", e);
-        } else {
-            info!("Platsbanken done!");
-        }
+        get_jobs_pb(&pb_ctx, &pb_duration)
+            .await
+            .unwrap_or_else(|e| ScrapeReport::failed("pb", e.to_string()))
     });
 
     let external_handle = tokio::spawn(async move {
-        if let Err(e) = get_jobs_external(&external_duration).await {
-            error!("Failed to get external jobs: {:?}", e);
-        } else {
-            info!("External done!");
-        }
+        get_jobs_external(&external_ctx, &external_duration)
+            .await
+            .unwrap_or_else(|e| ScrapeReport::failed("external", e.to_string()))
     });
 
     let abroad_handle = tokio::spawn(async move {
-        if let Err(e) = get_jobs_abroad(&abroad_duration).await {
-            error!("Failed to get abroad jobs: {:?}", e);
-        } else {
-            info!("Abroad jobs done!");
-        }
+        get_jobs_abroad(&abroad_ctx, &abroad_duration)
+            .await
+            .unwrap_or_else(|e| ScrapeReport::failed("abroad", e.to_string()))
     });
     let unspecified_handle = tokio::spawn(async move {
-        if let Err(e) = get_jobs_unspecified(&unspecified_duration).await {
-            error!("Failed to get unspecified jobs: {:?}", e);
-        } else {
-            info!("Unspecified done!");
-        }
+        get_jobs_unspecified(&unspecified_ctx, &unspecified_duration)
+            .await
+            .unwrap_or_else(|e| ScrapeReport::failed("unspecified", e.to_string()))
     });
 
-    let (pb_res, external_res, abroad_res, unspecificed_res) = tokio::try_join!(
+    let (pb_res, external_res, abroad_res, unspecified_res) = tokio::join!(
         pb_handle,
         external_handle,
         abroad_handle,
         unspecified_handle,
-    )?;
+    );
 
+    let reports = [
+        ("pb", pb_res),
+        ("external", external_res),
+        ("abroad", abroad_res),
+        ("unspecified", unspecified_res),
+    ]
+    .into_iter()
+    .map(|(source, joined)| {
+        joined.unwrap_or_else(|e| ScrapeReport::failed(source, format!("Task failed: {}", e)))
+    })
+    .collect::<Vec<_>>();
+
+    for report in reports.iter() {
+        match report.status {
+            ScrapeStatus::Success => info!("{}", report),
+            _ => error!("{}", report),
+        }
+    }
     info!("All jobs fetched!");
-    Ok(())
+    reports
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::MAX_DETAILS_ATTEMPTS;
+    use crate::database::SearchQuery;
+    use crate::mock_api::{ad, external_ad, MockSearchApi};
+    use crate::test_fixtures::job;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn scrape_stores_jobs_from_both_sources() {
+        let api = MockSearchApi::start().await;
+        api.page(
+            SOURCE_PB,
+            0,
+            vec![ad("pipeline-1", "Pipeline Rustutvecklare")],
+        )
+        .await;
+        api.page(
+            SOURCE_EXTERNAL,
+            0,
+            vec![external_ad(
+                "pipeline-2",
+                "Pipeline Backendutvecklare",
+                "https://example.com/jobb/2",
+            )],
+        )
+        .await;
+
+        get_jobs_with_filters(
+            &api.ctx,
+            &SearchDuration::Daily,
+            &[SearchFilter::Freetext("utvecklare".into())],
+        )
+        .await
+        .unwrap();
+
+        let mut jobs = api
+            .ctx
+            .store
+            .query_jobs(SearchQuery::Title(vec!["Pipeline".into()]))
+            .await
+            .unwrap();
+        jobs.sort_by(|a, b| a.id.cmp(&b.id));
+        assert_eq!(jobs.len(), 2);
+        assert_eq!(jobs[0].source.as_deref(), Some("Platsbanken"));
+        assert_eq!(
+            jobs[0].url.as_deref(),
+            Some(format!("{}pipeline-1", URL_JOB_ADS).as_str())
+        );
+        assert_eq!(jobs[0].published_date.as_deref(), Some("2026-10-18"));
+        assert_eq!(jobs[1].source.as_deref(), Some("External"));
+        assert_eq!(jobs[1].url.as_deref(), Some("https://example.com/jobb/2"));
+    }
+
+    #[tokio::test]
+    async fn scrape_follows_pagination_until_empty_page() {
+        let api = MockSearchApi::start().await;
+        let first_page = (0..PAGE_SIZE)
+            .map(|i| ad(&format!("paged-{}", i), "Paginerad annons"))
+            .collect();
+        api.page(SOURCE_PB, 0, first_page).await;
+        api.page(
+            SOURCE_PB,
+            PAGE_SIZE,
+            vec![ad("paged-last", "Paginerad annons")],
+        )
+        .await;
+
+        get_jobs_with_filters(
+            &api.ctx,
+            &SearchDuration::Max,
+            &[SearchFilter::Region(Region::Skåne)],
+        )
+        .await
+        .unwrap();
+
+        let jobs = api
+            .ctx
+            .store
+            .query_jobs(SearchQuery::Title(vec!["Paginerad".into()]))
+            .await
+            .unwrap();
+        assert_eq!(jobs.len(), PAGE_SIZE + 1);
+        // Three pages for pb and one empty page for external
+        assert_eq!(api.received_requests().await, 4);
+    }
+
+    #[tokio::test]
+    async fn scrape_with_only_empty_pages_inserts_nothing() {
+        let api = MockSearchApi::start().await;
+
+        let report = get_jobs_with_filters(
+            &api.ctx,
+            &SearchDuration::Weekly,
+            &[SearchFilter::Freetext("tomt".into())],
+        )
+        .await
+        .unwrap();
+        assert_eq!(report.status, ScrapeStatus::Success);
+
+        let jobs = api
+            .ctx
+            .store
+            .query_jobs(SearchQuery::Title(vec!["Tom sökning".into()]))
+            .await
+            .unwrap();
+        assert!(jobs.is_empty());
+        assert_eq!(api.received_requests().await, 2);
+    }
+
+    #[test]
+    fn failed_run_without_stored_ads_is_not_partial() {
+        let run = ScrapeRun {
+            source: "pb".to_string(),
+            ads_received: 100,
+            error: Some("Failed to store ads".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(ScrapeReport::from(&run).status, ScrapeStatus::Failed);
+    }
+
+    #[tokio::test]
+    async fn scrape_keeps_stored_pages_on_server_error() {
+        let api = MockSearchApi::start().await;
+        api.page(SOURCE_PB, 0, vec![ad("failing-1", "Felande annons")])
+            .await;
+        api.error(SOURCE_PB, PAGE_SIZE, 500).await;
+
+        let report = get_jobs_with_filters(
+            &api.ctx,
+            &SearchDuration::Daily,
+            &[SearchFilter::Freetext("fel".into())],
+        )
+        .await
+        .unwrap();
+        assert_eq!(report.status, ScrapeStatus::Partial);
+        assert!(report.error.is_some());
+
+        let jobs = api
+            .ctx
+            .store
+            .query_jobs(SearchQuery::Title(vec!["Felande".into()]))
+            .await
+            .unwrap();
+        assert_eq!(jobs.len(), 1);
+
+        let runs = api.ctx.store.scrape_runs(1).await.unwrap();
+        assert_eq!(runs[0].source, "filters");
+        assert_eq!(runs[0].pages_fetched, 1);
+        assert_eq!(runs[0].ads_inserted, 1);
+        assert!(runs[0].error.is_some());
+        assert!(runs[0].finished_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn get_all_jobs_reports_every_source() {
+        let api = MockSearchApi::start().await;
+        api.error(SOURCE_EXTERNAL, 0, 503).await;
+
+        let reports = get_all_jobs(&api.ctx, &SearchDuration::Daily).await;
+
+        let statuses = reports
+            .iter()
+            .map(|report| (report.source.as_str(), report.status))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            statuses,
+            vec![
+                ("pb", ScrapeStatus::Success),
+                ("external", ScrapeStatus::Failed),
+                ("abroad", ScrapeStatus::Success),
+                ("unspecified", ScrapeStatus::Success),
+            ]
+        );
+        assert_eq!(api.ctx.store.scrape_runs(10).await.unwrap().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn fetches_descriptions_and_salaries() {
+        let api = MockSearchApi::start().await;
+        api.page(SOURCE_PB, 0, vec![ad("details-1", "Detaljerad annons")])
+            .await;
+        api.details(
+            "details-1",
+            json!({
+                "id": "details-1",
+                "description": "<p>Vi söker en <b>utvecklare</b> som arbetar hybrid</p>",
+                "salaryType": "Fast månadslön",
+                "salaryDescription": "45 000 - 55 000 kr",
+            }),
+        )
+        .await;
+        get_jobs_with_filters(
+            &api.ctx,
+            &SearchDuration::Daily,
+            &[SearchFilter::Freetext("detaljerad".into())],
+        )
+        .await
+        .unwrap();
+
+        fetch_missing_details(&api.ctx, 10).await.unwrap();
+
+        let job = api.ctx.store.get_job("details-1").await.unwrap().unwrap();
+        assert_eq!(
+            job.description.as_deref(),
+            Some("Vi söker en utvecklare som arbetar hybrid")
+        );
+        assert_eq!(job.salary_type.as_deref(), Some("fixed"));
+        assert_eq!(
+            (job.salary_min, job.salary_max),
+            (Some(45_000), Some(55_000))
+        );
+        assert_eq!(job.salary_period.as_deref(), Some("month"));
+        assert_eq!(job.salary_monthly, Some(55_000));
+        assert_eq!(job.language.as_deref(), Some("sv"));
+        assert_eq!(job.work_mode.as_deref(), Some("hybrid"));
+        let missing = api.ctx.store.job_ids_without_details(10).await.unwrap();
+        assert!(missing.is_empty());
+    }
+
+    #[tokio::test]
+    async fn fetched_descriptions_are_classified_with_the_title() {
+        let api = MockSearchApi::start().await;
+        api.ctx
+            .store
+            .insert_jobs(&[job("english")
+                .title("We are looking for a remote developer")
+                .build()])
+            .await
+            .unwrap();
+        api.details(
+            "english",
+            json!({ "id": "english", "description": "Rust, Kubernetes, AWS" }),
+        )
+        .await;
+
+        fetch_missing_details(&api.ctx, 10).await.unwrap();
+
+        let job = api.ctx.store.get_job("english").await.unwrap().unwrap();
+        assert_eq!(job.language.as_deref(), Some("en"));
+        // Remote work at a named workplace is hybrid, the description alone would be on-site
+        assert_eq!(job.work_mode.as_deref(), Some("hybrid"));
+    }
+
+    #[tokio::test]
+    async fn ads_without_a_description_or_with_failing_details_are_not_fetched_forever() {
+        let api = MockSearchApi::start().await;
+        api.ctx
+            .store
+            .insert_jobs(&[job("no-description").build(), job("broken").build()])
+            .await
+            .unwrap();
+        api.details("no-description", json!({ "id": "no-description" }))
+            .await;
+
+        for _ in 0..MAX_DETAILS_ATTEMPTS {
+            fetch_missing_details(&api.ctx, 10).await.unwrap();
+        }
+
+        let job = api
+            .ctx
+            .store
+            .get_job("no-description")
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(job.description.is_none());
+        let missing = api.ctx.store.job_ids_without_details(10).await.unwrap();
+        assert!(missing.is_empty());
+        // One request for the ad without a description and one per attempt for the broken ad
+        assert_eq!(
+            api.received_requests().await,
+            1 + MAX_DETAILS_ATTEMPTS as usize
+        );
+    }
 }
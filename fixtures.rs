@@ -0,0 +1,120 @@
+//! Record and replay of search requests for reproducing scrapes.
+use chrono::DateTime;
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FixtureMode {
+    Live,
+    Record(PathBuf),
+    Replay(PathBuf),
+}
+
+// fromDate and toDate move with the time of the run, so a fixture keeps only the number of days
+// between them. The rest of the payload identifies the fixture as is, hashed since the filters
+// can be longer than a file name allows.
+pub fn fixture_name(payload: &Value) -> String {
+    let mut canonical = payload.clone();
+    if let Some(fields) = canonical.as_object_mut() {
+        let from_date = fields.remove("fromDate");
+        let to_date = fields.remove("toDate");
+        fields.insert(
+            "days".to_string(),
+            json!(days_between(from_date.as_ref(), to_date.as_ref())),
+        );
+    }
+    let source = payload["source"].as_str().unwrap_or("unknown");
+    let start_index = payload["startIndex"].as_u64().unwrap_or_default();
+    let source = source
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    // Objects serialize with sorted keys, so equal payloads hash the same
+    format!(
+        "{}_{}_{:016x}",
+        source,
+        start_index,
+        fnv1a(canonical.to_string().as_bytes())
+    )
+}
+
+fn days_between(from_date: Option<&Value>, to_date: Option<&Value>) -> Option<i64> {
+    let parse = |date: &Value| DateTime::parse_from_rfc3339(date.as_str()?).ok();
+    let seconds = (parse(to_date?)? - parse(from_date?)?).num_seconds();
+    // Both dates are taken from the clock one after the other, round off the difference
+    Some((seconds + 43_200).div_euclid(86_400))
+}
+
+// 64-bit FNV-1a, unlike the std hashers it is stable across Rust versions
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+pub fn record(dir: &Path, payload: &Value, body: &str) -> Result<(), std::io::Error> {
+    std::fs::create_dir_all(dir)?;
+    let name = fixture_name(payload);
+    std::fs::write(
+        dir.join(format!("{}.request.json", name)),
+        serde_json::to_string_pretty(payload)?,
+    )?;
+    std::fs::write(dir.join(format!("{}.response.json", name)), body)?;
+    Ok(())
+}
+
+pub fn replay(dir: &Path, payload: &Value) -> Result<String, std::io::Error> {
+    let path = dir.join(format!("{}.response.json", fixture_name(payload)));
+    std::fs::read_to_string(&path).map_err(|e| {
+        std::io::Error::new(
+            e.kind(),
+            format!("No recorded response at {}: {}", path.display(), e),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replays_recorded_response_regardless_of_dates() {
+        let dir = std::env::temp_dir().join(format!("pb-fixtures-{}", std::process::id()));
+        let payload = json!({
+            "filters": [{ "type": "region", "value": "CifL_Rzy_Mku" }],
+            "fromDate": "2026-10-17T08:00:00.000Z",
+            "toDate": "2026-10-18T08:00:00.000Z",
+            "startIndex": 100,
+            "source": "pb",
+        });
+        record(&dir, &payload, r#"{"ads":[],"numberOfAds":0}"#).unwrap();
+
+        let mut later = payload.clone();
+        later["fromDate"] = json!("2026-10-18T08:00:00.001Z");
+        later["toDate"] = json!("2026-10-19T08:00:00.000Z");
+        assert_eq!(
+            replay(&dir, &later).unwrap(),
+            r#"{"ads":[],"numberOfAds":0}"#
+        );
+
+        let mut next_page = payload.clone();
+        next_page["startIndex"] = json!(200);
+        assert!(replay(&dir, &next_page).is_err());
+
+        let mut weekly = payload.clone();
+        weekly["fromDate"] = json!("2026-10-11T08:00:00.000Z");
+        assert!(replay(&dir, &weekly).is_err());
+
+        let mut larger_pages = payload.clone();
+        larger_pages["maxRecords"] = json!(200);
+        assert!(replay(&dir, &larger_pages).is_err());
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+}
@@ -0,0 +1,236 @@
+//! Liveness and readiness checks served on `/healthz` and `/readyz` by [`crate::server`].
+use crate::constants::{SCRAPE_MAX_AGE_HOURS, TELEGRAM_ERROR_MAX_AGE_MINUTES};
+use crate::context::AppContext;
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+/// Polling state of the Telegram bot, only checked in processes that run the bot.
+#[derive(Debug, Default)]
+pub struct TelegramStatus {
+    enabled: AtomicBool,
+    polling: AtomicBool,
+    last_update: Mutex<Option<DateTime<Utc>>>,
+    last_error: Mutex<Option<DateTime<Utc>>>,
+}
+
+impl TelegramStatus {
+    pub fn set_polling(&self, polling: bool) {
+        self.enabled.store(true, Ordering::Relaxed);
+        self.polling.store(polling, Ordering::Relaxed);
+    }
+
+    /// Receiving an update proves polling works, even after a failed start.
+    pub fn record_update(&self) {
+        self.set_polling(true);
+        if let Ok(mut last_update) = self.last_update.lock() {
+            *last_update = Some(Utc::now());
+        }
+    }
+
+    /// A failed poll makes the bot unready until an update arrives or polling has kept working
+    /// for [`TELEGRAM_ERROR_MAX_AGE_MINUTES`], teloxide retries failed polls at least every
+    /// minute.
+    pub fn record_error(&self) {
+        if let Ok(mut last_error) = self.last_error.lock() {
+            *last_error = Some(Utc::now());
+        }
+    }
+
+    fn check(&self) -> Option<TelegramCheck> {
+        if !self.enabled.load(Ordering::Relaxed) {
+            return None;
+        }
+        let last_update = self.last_update.lock().ok().and_then(|date| *date);
+        let last_error = self.last_error.lock().ok().and_then(|date| *date);
+        let polling = self.polling.load(Ordering::Relaxed)
+            && last_error.is_none_or(|error| {
+                last_update.is_some_and(|update| update > error)
+                    || Utc::now() - error > Duration::minutes(TELEGRAM_ERROR_MAX_AGE_MINUTES)
+            });
+        Some(TelegramCheck {
+            ok: polling,
+            polling,
+            last_update: last_update.map(format_date),
+            last_error: last_error.map(format_date),
+        })
+    }
+}
+
+fn format_date(date: DateTime<Utc>) -> String {
+    date.format("%Y-%m-%dT%H:%M:%S.%3fZ").to_string()
+}
+
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub ok: bool,
+    pub database: DatabaseCheck,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub scrapes: Vec<ScrapeCheck>,
+    /// Error reading the last successful scrapes, the scrapes are not checked then.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scrapes_error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub telegram: Option<TelegramCheck>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DatabaseCheck {
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ScrapeCheck {
+    pub source: String,
+    pub ok: bool,
+    pub last_success: String,
+    pub age_seconds: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TelegramCheck {
+    pub ok: bool,
+    pub polling: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_update: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+}
+
+async fn check_database(ctx: &AppContext) -> DatabaseCheck {
+    match ctx.store.ping().await {
+        Ok(()) => DatabaseCheck {
+            ok: true,
+            error: None,
+        },
+        Err(e) => DatabaseCheck {
+            ok: false,
+            error: Some(e.to_string()),
+        },
+    }
+}
+
+/// The process is alive and can reach its database.
+pub async fn liveness(ctx: &AppContext) -> Report {
+    let database = check_database(ctx).await;
+    Report {
+        ok: database.ok,
+        database,
+        scrapes: Vec::new(),
+        scrapes_error: None,
+        telegram: None,
+    }
+}
+
+/// Liveness plus fresh scrapes and, in the bot, a polling Telegram connection. Sources that
+/// never had a successful scrape are not listed, so a new database is ready.
+pub async fn readiness(ctx: &AppContext) -> Report {
+    let database = check_database(ctx).await;
+    let now = Utc::now();
+    let (runs, scrapes_error) = if database.ok {
+        match ctx.store.last_successful_runs().await {
+            Ok(runs) => (runs, None),
+            Err(e) => (Vec::new(), Some(e.to_string())),
+        }
+    } else {
+        (Vec::new(), None)
+    };
+
+    let scrapes = runs
+        .into_iter()
+        .filter_map(|run| {
+            let finished_at = DateTime::parse_from_rfc3339(run.finished_at.as_deref()?).ok()?;
+            let age = now - finished_at.with_timezone(&Utc);
+            Some(ScrapeCheck {
+                source: run.source,
+                ok: age <= Duration::hours(SCRAPE_MAX_AGE_HOURS),
+                last_success: finished_at.to_rfc3339(),
+                age_seconds: age.num_seconds(),
+            })
+        })
+        .collect::<Vec<_>>();
+    let telegram = ctx.telegram.check();
+
+    Report {
+        ok: database.ok
+            && scrapes_error.is_none()
+            && scrapes.iter().all(|scrape| scrape.ok)
+            && telegram.as_ref().is_none_or(|telegram| telegram.ok),
+        database,
+        scrapes,
+        scrapes_error,
+        telegram,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::ScrapeRun;
+    use crate::test_fixtures::context;
+
+    async fn finished_run(ctx: &AppContext, source: &str, finished_at: DateTime<Utc>) {
+        let mut run = ScrapeRun {
+            started_at: finished_at.format("%Y-%m-%dT%H:%M:%S.%3fZ").to_string(),
+            source: source.to_string(),
+            ..Default::default()
+        };
+        run.id = ctx.store.start_scrape_run(&run).await.unwrap();
+        run.finished_at = Some(run.started_at.clone());
+        ctx.store.finish_scrape_run(&run).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn new_database_is_ready() {
+        let ctx = context().await;
+        let report = readiness(&ctx).await;
+        assert!(report.ok);
+        assert!(report.scrapes.is_empty());
+        assert!(report.telegram.is_none());
+    }
+
+    #[tokio::test]
+    async fn stale_scrape_is_not_ready() {
+        let ctx = context().await;
+        finished_run(&ctx, "pb", Utc::now() - Duration::hours(1)).await;
+        finished_run(&ctx, "external", Utc::now() - Duration::hours(48)).await;
+
+        let report = readiness(&ctx).await;
+        assert!(!report.ok);
+        let stale = report
+            .scrapes
+            .iter()
+            .filter(|scrape| !scrape.ok)
+            .map(|scrape| scrape.source.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(stale, vec!["external"]);
+    }
+
+    #[tokio::test]
+    async fn stopped_bot_is_not_ready() {
+        let ctx = context().await;
+        ctx.telegram.set_polling(true);
+        assert!(readiness(&ctx).await.ok);
+
+        ctx.telegram.set_polling(false);
+        let report = readiness(&ctx).await;
+        assert!(!report.ok);
+        assert!(report.database.ok);
+    }
+
+    #[tokio::test]
+    async fn polling_error_is_not_ready_until_the_next_update() {
+        let ctx = context().await;
+        ctx.telegram.set_polling(true);
+        ctx.telegram.record_error();
+        let report = readiness(&ctx).await;
+        assert!(!report.ok);
+        assert!(report.telegram.unwrap().last_error.is_some());
+
+        ctx.telegram.record_update();
+        assert!(readiness(&ctx).await.ok);
+    }
+}
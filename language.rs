@@ -0,0 +1,135 @@
+//! Offline detection of the language an ad is written in, from the share of common Swedish and
+//! English words in its title and description.
+use crate::context::AppContext;
+use crate::database::SearchQuery;
+use crate::logging::info;
+use serde::{Deserialize, Serialize};
+use strum_macros::{Display, EnumIter, EnumString};
+
+const SWEDISH_WORDS: &str =
+    "och att som för med på är av det till ett en vi du har kommer kan eller inom hos om \
+    den vara sig dig oss vår våra din ditt samt inte också även hur mer arbete erfarenhet \
+    tjänsten söker sökes ansökan utvecklare medarbetare";
+
+const ENGLISH_WORDS: &str =
+    "the and of to in is we you our with for are will be as this that have your who an or \
+    on work experience skills looking role developer engineer manager";
+
+// Frequent words of Norwegian, Danish, German, Finnish, French and Spanish that are neither
+// Swedish nor English
+const OTHER_WORDS: &str =
+    "og ikke til hvor hvis jeg være und der die das mit nicht ist wir auf für että olla \
+    kanssa les et pour avec los las para con por";
+
+fn is_listed(words: &str, word: &str) -> bool {
+    words.split_whitespace().any(|listed| listed == word)
+}
+
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, Hash, Display, EnumString, EnumIter, Serialize, Deserialize,
+)]
+pub enum Language {
+    #[strum(serialize = "sv")]
+    #[serde(rename = "sv")]
+    Swedish,
+    #[strum(serialize = "en")]
+    #[serde(rename = "en")]
+    English,
+    #[strum(serialize = "other")]
+    #[serde(rename = "other")]
+    Other,
+}
+
+impl Language {
+    /// The language with the most common words in the title and description, Swedish on a tie
+    /// since most ads are Swedish. `None` when the text has no common words of any language,
+    /// such as a title of a single word.
+    pub fn detect(title: Option<&str>, description: Option<&str>) -> Option<Language> {
+        let text = format!(
+            "{} {}",
+            title.unwrap_or_default(),
+            description.unwrap_or_default()
+        )
+        .to_lowercase();
+
+        let (mut swedish, mut english, mut other) = (0, 0, 0);
+        for word in text.split(|c: char| !c.is_alphabetic()) {
+            if word.is_empty() {
+                continue;
+            }
+            if is_listed(SWEDISH_WORDS, word) {
+                swedish += 1;
+            } else if is_listed(ENGLISH_WORDS, word) {
+                english += 1;
+            } else if is_listed(OTHER_WORDS, word) || word.contains(['ø', 'æ', 'ü', 'ß']) {
+                other += 1;
+            } else if word.contains(['å', 'ä', 'ö']) {
+                swedish += 1;
+            }
+        }
+
+        match (swedish, english, other) {
+            (0, 0, 0) => None,
+            _ if swedish >= english && swedish >= other => Some(Language::Swedish),
+            _ if english >= other => Some(Language::English),
+            _ => Some(Language::Other),
+        }
+    }
+}
+
+/// Detects the language of every stored ad again, for ads stored before languages were detected.
+pub async fn detect_languages(ctx: &AppContext) -> Result<usize, sqlx::Error> {
+    let jobs = ctx.store.query_jobs(SearchQuery::All).await?;
+    for job in jobs.iter() {
+        let Some(id) = job.id.as_deref() else {
+            continue;
+        };
+        let language = Language::detect(job.title.as_deref(), job.description.as_deref());
+        if job.language != language.map(|language| language.to_string()) {
+            ctx.store.update_language(id, language).await?;
+        }
+    }
+    info!("Detected the language of {} ads", jobs.len());
+    Ok(jobs.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_swedish_and_english_ads() {
+        assert_eq!(
+            Language::detect(
+                Some("Systemutvecklare"),
+                Some("Vi söker dig som har erfarenhet av Rust och vill arbeta i ett team.")
+            ),
+            Some(Language::Swedish)
+        );
+        assert_eq!(
+            Language::detect(
+                Some("Backend Developer"),
+                Some("We are looking for an engineer with experience in Rust to join our team.")
+            ),
+            Some(Language::English)
+        );
+        assert_eq!(
+            Language::detect(Some("Lagerarbetare till Göteborg"), None),
+            Some(Language::Swedish)
+        );
+    }
+
+    #[test]
+    fn other_languages_and_too_little_text() {
+        assert_eq!(
+            Language::detect(
+                None,
+                Some("Wir suchen einen Entwickler mit Erfahrung und Freude an der Arbeit.")
+            ),
+            Some(Language::Other)
+        );
+        assert_eq!(Language::detect(Some("Systemutvecklare"), None), None);
+        assert_eq!("en".parse::<Language>().unwrap(), Language::English);
+        assert_eq!(Language::Other.to_string(), "other");
+    }
+}
@@ -0,0 +1,41 @@
+//! Scraper and storage for job ads from Platsbanken, Sweden's public job listing service.
+//!
+//! [`find_jobs`] fetches ads from the Platsbanken search API, [`database`] stores and queries
+//! them in SQLite or PostgreSQL through the backends in [`store`] and [`export`] writes query
+//! results to files. The Telegram bot in [`bot`] builds on the same storage to notify users
+//! about new ads.
+
+pub mod api;
+pub mod bot;
+pub mod constants;
+pub mod context;
+pub mod database;
+pub mod employers;
+pub mod export;
+pub mod find_jobs;
+pub mod fixtures;
+pub mod health;
+pub mod language;
+pub mod logging;
+pub mod metrics;
+#[cfg(test)]
+mod mock_api;
+pub mod municipality;
+pub mod pipeline;
+pub mod salary;
+pub mod server;
+pub mod skills;
+pub mod stats;
+pub mod store;
+pub mod subscription;
+pub mod taxonomy;
+#[cfg(test)]
+mod test_fixtures;
+pub mod web;
+pub mod work_mode;
+
+pub use database::{DbJobAd, SearchQuery};
+pub use export::SaveToCsv;
+pub use find_jobs::{
+    JobAd, OccupationType, Region, ScrapeReport, ScrapeStatus, SearchDuration, SearchFilter,
+};
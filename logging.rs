@@ -1,3 +1,4 @@
+//! Logger setup shared by the binaries.
 use flexi_logger::{Age, Cleanup, Criterion, Duplicate, FileSpec, Logger, Naming, WriteMode};
 pub use log::{error, info};
 
@@ -1,30 +1,283 @@
-#![allow(unused_variables)]
-#![allow(unused_must_use)]
-#![allow(dead_code)]
-#![allow(unused_imports)]
-
-use crate::database::{connect_database, SearchQuery};
-use crate::find_jobs::SearchDuration;
-use crate::logging::{error, setup_log};
-use bot::run_bot;
-use database::SaveToCsv;
-use log::info;
-use tokio;
-
-mod bot;
-mod constants;
-mod database;
-mod find_jobs;
-mod logging;
+use crate::cli::{Cli, Commands};
+use chrono::DateTime;
+use clap::Parser;
+use platsbanken::context::{AppContext, Config};
+use platsbanken::database::ScrapeRun;
+use platsbanken::employers::{self, EmployerQuery, EmployerReport};
+use platsbanken::fixtures::FixtureMode;
+use platsbanken::language;
+use platsbanken::logging::{error, info, setup_log};
+use platsbanken::salary::{self, SalaryQuery, SalaryReport};
+use platsbanken::skills::{self, RegionSkillQuery, RegionSkillReport, SkillQuery, SkillTrends};
+use platsbanken::stats::{self, OutputFormat, TrendQuery, Trends};
+use platsbanken::work_mode;
+use platsbanken::{
+    find_jobs, municipality, server, taxonomy, SaveToCsv, ScrapeStatus, SearchFilter, SearchQuery,
+};
+
+use serde::Serialize;
+use std::io::Stdout;
+
+mod cli;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let cli = Cli::parse();
     setup_log().await?;
-    connect_database().await?;
 
-    // find_jobs::get_all_jobs(&SearchDuration::Weekly).await?;
+    let mut config = Config::from_env()?;
+    config.fixture_mode = match (cli.record.clone(), cli.replay.clone()) {
+        (Some(dir), _) => FixtureMode::Record(dir),
+        (_, Some(dir)) => FixtureMode::Replay(dir),
+        _ => FixtureMode::Live,
+    };
+    if let FixtureMode::Record(dir) | FixtureMode::Replay(dir) = &config.fixture_mode {
+        info!(
+            "Using fixture mode {:?} in {}",
+            config.fixture_mode,
+            dir.display()
+        );
+    }
+
+    if let Some(Commands::RefreshTaxonomy) = cli.command {
+        taxonomy::refresh_taxonomy().await?;
+        return Ok(());
+    }
+    taxonomy::init_taxonomy()?;
+
+    let ctx = AppContext::new(config).await?;
+
+    match cli.command {
+        // Handled before the taxonomy is loaded
+        Some(Commands::RefreshTaxonomy) => {}
+        Some(Commands::Runs { limit }) => print_runs(&ctx.store.scrape_runs(limit).await?),
+        Some(Commands::Details { limit }) => {
+            find_jobs::fetch_missing_details(&ctx, limit).await?;
+        }
+        Some(Commands::Stats {
+            by,
+            interval,
+            periods,
+            top,
+            format,
+        }) => {
+            let query = TrendQuery {
+                by,
+                interval,
+                periods,
+                top,
+            };
+            let trends = stats::trends(&ctx, &query).await?;
+            print_report(&trends, format, Trends::to_table, Trends::write_csv)?;
+        }
+        Some(Commands::Skills {
+            interval,
+            periods,
+            top,
+            region,
+            format,
+        }) => {
+            let query = SkillQuery {
+                interval,
+                periods,
+                top,
+                region,
+            };
+            let trends = skills::skill_trends(&ctx, &query).await?;
+            print_report(
+                &trends,
+                format,
+                SkillTrends::to_table,
+                SkillTrends::write_csv,
+            )?;
+        }
+        Some(Commands::SkillRegions { days, top, format }) => {
+            let report = skills::region_skills(&ctx, &RegionSkillQuery { days, top }).await?;
+            print_report(
+                &report,
+                format,
+                RegionSkillReport::to_table,
+                RegionSkillReport::write_csv,
+            )?;
+        }
+        Some(Commands::ExtractSkills) => {
+            skills::reextract_skills(&ctx).await?;
+        }
+        Some(Commands::DetectLanguages) => {
+            language::detect_languages(&ctx).await?;
+        }
+        Some(Commands::ClassifyWorkModes) => {
+            work_mode::classify_work_modes(&ctx).await?;
+        }
+        Some(Commands::Salaries {
+            by,
+            days,
+            top,
+            format,
+        }) => {
+            let query = SalaryQuery { by, days, top };
+            let report = salary::salary_report(&ctx, &query).await?;
+            print_report(
+                &report,
+                format,
+                SalaryReport::to_table,
+                SalaryReport::write_csv,
+            )?;
+        }
+        Some(Commands::Employers {
+            days,
+            top,
+            region,
+            occupation,
+            format,
+        }) => {
+            let query = EmployerQuery {
+                days,
+                top,
+                region,
+                occupation,
+            };
+            let report = employers::employer_report(&ctx, &query).await?;
+            print_report(
+                &report,
+                format,
+                EmployerReport::to_table,
+                EmployerReport::write_csv,
+            )?;
+        }
+        Some(Commands::Scrape {
+            duration,
+            occupation,
+            region,
+            municipality,
+            freetext,
+            details,
+        }) => {
+            // Only scrapes run long enough to serve metrics, the other commands exit right away
+            // and would clash with a daemon on the same address
+            server::spawn(&ctx);
+
+            let mut filters = Vec::new();
+            if let Some(occupation) = occupation {
+                let filter = SearchFilter::occupation_field(&occupation).ok_or_else(|| {
+                    format!(
+                        "No concept id for {:?}, run refresh-taxonomy first",
+                        occupation
+                    )
+                })?;
+                filters.push(filter);
+            }
+            if let Some(region) = region {
+                filters.push(SearchFilter::Region(region));
+            }
+            if let Some(name) = municipality {
+                let municipality = municipality::find_municipality(&name)
+                    .ok_or_else(|| format!("Unknown municipality {}", name))?;
+                filters.push(SearchFilter::Municipality(municipality.clone()));
+            }
+            if let Some(text) = freetext {
+                filters.push(SearchFilter::Freetext(text));
+            }
+
+            let reports = if filters.is_empty() {
+                find_jobs::get_all_jobs(&ctx, &duration).await
+            } else {
+                vec![find_jobs::get_jobs_with_filters(&ctx, &duration, &filters).await?]
+            };
+            for report in reports.iter() {
+                println!("{}", report);
+            }
 
-    let res = database::get_jobs_by_query(SearchQuery::Title(vec!["Data".to_string()])).await?;
-    res.save_to_csv("jobs.csv")?;
+            // Keyword watches also match descriptions, which the search results do not include
+            if details > 0 {
+                if let Err(e) = find_jobs::fetch_missing_details(&ctx, details).await {
+                    error!("Failed to fetch details: {:?}", e);
+                }
+            }
+
+            // 1 when a source failed, 2 when a source failed after storing some of its ads
+            match reports.iter().map(|report| report.status).max() {
+                Some(ScrapeStatus::Failed) => std::process::exit(1),
+                Some(ScrapeStatus::Partial) => std::process::exit(2),
+                _ => {}
+            }
+        }
+        None => {
+            // find_jobs::get_all_jobs(&ctx, &SearchDuration::Weekly).await;
+
+            let res = ctx
+                .store
+                .query_jobs(SearchQuery::Title(vec!["Data".to_string()]))
+                .await?;
+            res.save_to_csv("jobs.csv")?;
+        }
+    }
     Ok(())
 }
+
+fn print_report<T: Serialize>(
+    report: &T,
+    format: OutputFormat,
+    to_table: fn(&T) -> String,
+    write_csv: fn(&T, Stdout) -> csv::Result<()>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match format {
+        OutputFormat::Table => print!("{}", to_table(report)),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(report)?),
+        OutputFormat::Csv => write_csv(report, std::io::stdout())?,
+    }
+    Ok(())
+}
+
+fn print_runs(runs: &[ScrapeRun]) {
+    let header = [
+        "ID",
+        "STARTED",
+        "SECONDS",
+        "SOURCE",
+        "PAGES",
+        "ADS/AVAILABLE",
+        "INSERTED",
+        "UPDATED",
+        "RESULT",
+    ];
+    println!(
+        "{:>6}  {:<24}  {:>8}  {:<12}  {:>5}  {:>13}  {:>8}  {:>8}  {}",
+        header[0],
+        header[1],
+        header[2],
+        header[3],
+        header[4],
+        header[5],
+        header[6],
+        header[7],
+        header[8]
+    );
+    for run in runs {
+        let seconds = run
+            .finished_at
+            .as_deref()
+            .and_then(|finished| DateTime::parse_from_rfc3339(finished).ok())
+            .zip(DateTime::parse_from_rfc3339(&run.started_at).ok())
+            .map(|(finished, started)| (finished - started).num_seconds().to_string())
+            .unwrap_or_else(|| "-".to_string());
+        let result = match (&run.finished_at, &run.error) {
+            (_, Some(error)) => error.as_str(),
+            (Some(_), None) => "ok",
+            (None, None) => "unfinished",
+        };
+        let ads = format!("{}/{}", run.ads_received, run.ads_available);
+        println!(
+            "{:>6}  {:<24}  {:>8}  {:<12}  {:>5}  {:>13}  {:>8}  {:>8}  {}",
+            run.id,
+            run.started_at,
+            seconds,
+            run.source,
+            run.pages_fetched,
+            ads,
+            run.ads_inserted,
+            run.ads_updated,
+            result
+        );
+    }
+}
@@ -0,0 +1,129 @@
+//! Prometheus metrics for the scraper and the bot, served on `/metrics` by [`crate::server`].
+use prometheus::{
+    Encoder, Histogram, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, Opts, Registry,
+    TextEncoder,
+};
+use std::sync::LazyLock;
+
+static REGISTRY: LazyLock<Registry> = LazyLock::new(Registry::new);
+
+/// Requests to the search API by HTTP status, `error` when no response was received.
+pub static SEARCH_REQUESTS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register(IntCounterVec::new(
+        Opts::new(
+            "platsbanken_search_requests_total",
+            "Requests to the Platsbanken search API",
+        ),
+        &["status"],
+    ))
+});
+
+/// Pages of ads fetched per scrape source.
+pub static PAGES_FETCHED: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register(IntCounterVec::new(
+        Opts::new(
+            "platsbanken_pages_fetched_total",
+            "Search result pages fetched",
+        ),
+        &["source"],
+    ))
+});
+
+/// Ads received per scrape source, `inserted` when new, `updated` when already stored and
+/// `dropped` when skipped for lacking an id or as a duplicate of another ad in the same batch.
+pub static ADS_WRITTEN: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register(IntCounterVec::new(
+        Opts::new(
+            "platsbanken_ads_written_total",
+            "Scraped ads handed to the database",
+        ),
+        &["source", "outcome"],
+    ))
+});
+
+pub static SCRAPE_DURATION: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register(HistogramVec::new(
+        HistogramOpts::new(
+            "platsbanken_scrape_duration_seconds",
+            "Duration of a scrape run per source",
+        )
+        .buckets(vec![
+            1.0, 5.0, 15.0, 30.0, 60.0, 120.0, 300.0, 600.0, 1800.0,
+        ]),
+        &["source"],
+    ))
+});
+
+pub static DB_WRITE_LATENCY: LazyLock<Histogram> = LazyLock::new(|| {
+    register(Histogram::with_opts(HistogramOpts::new(
+        "platsbanken_db_write_seconds",
+        "Time spent writing one batch of ads",
+    )))
+});
+
+/// Messages sent by the bot, `digest` for subscription pages and `reply` for answers to
+/// commands and buttons.
+pub static BOT_MESSAGES_SENT: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register(IntCounterVec::new(
+        Opts::new(
+            "platsbanken_bot_messages_sent_total",
+            "Telegram messages sent",
+        ),
+        &["kind"],
+    ))
+});
+
+/// Digest pages waiting for the user to press "Visa fler".
+pub static NOTIFICATION_BACKLOG: LazyLock<IntGauge> = LazyLock::new(|| {
+    register(IntGauge::new(
+        "platsbanken_notification_backlog",
+        "Digest pages not yet delivered",
+    ))
+});
+
+fn register<M>(metric: prometheus::Result<M>) -> M
+where
+    M: prometheus::core::Collector + Clone + 'static,
+{
+    let metric = metric.expect("Invalid metric definition");
+    REGISTRY
+        .register(Box::new(metric.clone()))
+        .expect("Metric registered twice");
+    metric
+}
+
+/// All metrics in the Prometheus text format.
+pub fn render() -> String {
+    // Metrics register on first use, touch them so they are listed before anything happened
+    LazyLock::force(&SEARCH_REQUESTS);
+    LazyLock::force(&PAGES_FETCHED);
+    LazyLock::force(&ADS_WRITTEN);
+    LazyLock::force(&SCRAPE_DURATION);
+    LazyLock::force(&DB_WRITE_LATENCY);
+    LazyLock::force(&BOT_MESSAGES_SENT);
+    LazyLock::force(&NOTIFICATION_BACKLOG);
+
+    let mut buffer = Vec::new();
+    if let Err(e) = TextEncoder::new().encode(&REGISTRY.gather(), &mut buffer) {
+        crate::logging::error!("Failed to encode metrics: {:?}", e);
+    }
+    String::from_utf8(buffer).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_lists_metrics_before_first_use() {
+        let text = render();
+        assert!(text.contains("platsbanken_notification_backlog 0"));
+        assert!(text.contains("# TYPE platsbanken_db_write_seconds histogram"));
+    }
+
+    #[test]
+    fn render_includes_labels() {
+        SEARCH_REQUESTS.with_label_values(&["418"]).inc();
+        assert!(render().contains(r#"platsbanken_search_requests_total{status="418"} 1"#));
+    }
+}
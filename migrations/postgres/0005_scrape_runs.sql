@@ -0,0 +1,15 @@
+CREATE TABLE IF NOT EXISTS scrape_runs (
+    id BIGSERIAL PRIMARY KEY,
+    started_at TEXT NOT NULL,
+    finished_at TEXT,
+    source TEXT NOT NULL,
+    filters TEXT,
+    pages_fetched BIGINT NOT NULL DEFAULT 0,
+    ads_received BIGINT NOT NULL DEFAULT 0,
+    ads_available BIGINT NOT NULL DEFAULT 0,
+    ads_inserted BIGINT NOT NULL DEFAULT 0,
+    ads_updated BIGINT NOT NULL DEFAULT 0,
+    error TEXT
+);
+
+CREATE INDEX IF NOT EXISTS scrape_runs_started_at ON scrape_runs (started_at);
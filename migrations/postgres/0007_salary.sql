@@ -0,0 +1,17 @@
+-- Salary from the ad details, parsed by the salary module. salary_monthly is the upper end of
+-- the range in kronor per month and backs the minimum salary filter.
+ALTER TABLE jobs ADD COLUMN salary_type TEXT;
+ALTER TABLE jobs ADD COLUMN salary_description TEXT;
+ALTER TABLE jobs ADD COLUMN salary_min BIGINT;
+ALTER TABLE jobs ADD COLUMN salary_max BIGINT;
+ALTER TABLE jobs ADD COLUMN salary_period TEXT;
+ALTER TABLE jobs ADD COLUMN salary_monthly BIGINT;
+
+CREATE INDEX IF NOT EXISTS jobs_salary_monthly ON jobs (salary_monthly);
+
+-- Time the details of the ad were fetched and the number of failed fetches, so ads without a
+-- description or with failing details are not fetched again on every run.
+ALTER TABLE jobs ADD COLUMN details_fetched_at TEXT;
+ALTER TABLE jobs ADD COLUMN details_attempts INTEGER NOT NULL DEFAULT 0;
+
+CREATE INDEX IF NOT EXISTS jobs_details_fetched_at ON jobs (details_fetched_at);
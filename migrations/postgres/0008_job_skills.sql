@@ -0,0 +1,9 @@
+-- Skills and technologies mentioned in the title or description of an ad, extracted with the
+-- dictionary of the skills module
+CREATE TABLE IF NOT EXISTS job_skills (
+    job_id TEXT NOT NULL REFERENCES jobs (id) ON DELETE CASCADE,
+    skill TEXT NOT NULL,
+    PRIMARY KEY (job_id, skill)
+);
+
+CREATE INDEX IF NOT EXISTS job_skills_skill ON job_skills (skill);
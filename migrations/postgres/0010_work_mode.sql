@@ -0,0 +1,6 @@
+-- "on_site", "hybrid" or "remote", classified by the work_mode module from the workplace and
+-- keywords in the title and description. Ads without a fetched description are left NULL. Ads
+-- stored before are classified by the classify-work-modes command.
+ALTER TABLE jobs ADD COLUMN work_mode TEXT;
+
+CREATE INDEX IF NOT EXISTS jobs_work_mode ON jobs (work_mode);
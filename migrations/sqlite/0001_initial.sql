@@ -0,0 +1,18 @@
+CREATE TABLE IF NOT EXISTS jobs (
+    id TEXT PRIMARY KEY NOT NULL,
+    title TEXT,
+    occupation TEXT,
+    workplace TEXT,
+    workplace_name TEXT,
+    published_date TEXT,
+    last_application_date TEXT,
+    source TEXT,
+    url TEXT,
+    created_at TEXT
+);
+
+CREATE TABLE IF NOT EXISTS users (
+    id TEXT PRIMARY KEY NOT NULL,
+    jobcategories TEXT,
+    regions TEXT
+);
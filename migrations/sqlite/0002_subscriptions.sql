@@ -0,0 +1,3 @@
+ALTER TABLE users ADD COLUMN frequency TEXT;
+ALTER TABLE users ADD COLUMN delivery_hour INTEGER;
+ALTER TABLE users ADD COLUMN last_delivered_at TEXT;
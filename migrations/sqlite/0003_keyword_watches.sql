@@ -0,0 +1,2 @@
+ALTER TABLE jobs ADD COLUMN description TEXT;
+ALTER TABLE users ADD COLUMN keyword_watches TEXT;
@@ -0,0 +1,15 @@
+CREATE TABLE IF NOT EXISTS scrape_runs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    started_at TEXT NOT NULL,
+    finished_at TEXT,
+    source TEXT NOT NULL,
+    filters TEXT,
+    pages_fetched INTEGER NOT NULL DEFAULT 0,
+    ads_received INTEGER NOT NULL DEFAULT 0,
+    ads_available INTEGER NOT NULL DEFAULT 0,
+    ads_inserted INTEGER NOT NULL DEFAULT 0,
+    ads_updated INTEGER NOT NULL DEFAULT 0,
+    error TEXT
+);
+
+CREATE INDEX IF NOT EXISTS scrape_runs_started_at ON scrape_runs (started_at);
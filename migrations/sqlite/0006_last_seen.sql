@@ -0,0 +1,3 @@
+-- Time of the latest scrape that returned the ad, ads already stored were last seen when created
+ALTER TABLE jobs ADD COLUMN last_seen_at TEXT;
+UPDATE jobs SET last_seen_at = created_at;
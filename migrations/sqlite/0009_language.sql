@@ -0,0 +1,5 @@
+-- Language of the title and description, "sv", "en" or "other", detected by the language module.
+-- Ads stored before are detected by the detect-languages command.
+ALTER TABLE jobs ADD COLUMN language TEXT;
+
+CREATE INDEX IF NOT EXISTS jobs_language ON jobs (language);
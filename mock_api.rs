@@ -0,0 +1,106 @@
+use crate::context::{AppContext, Config};
+use crate::fixtures::FixtureMode;
+use crate::store::SqliteStore;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use wiremock::matchers::{body_partial_json, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+pub struct MockSearchApi {
+    server: MockServer,
+    pub ctx: AppContext,
+}
+
+impl MockSearchApi {
+    /// Starts a mock server and a context with its own in-memory database pointing at it.
+    pub async fn start() -> Self {
+        let server = MockServer::start().await;
+        let store = SqliteStore::in_memory()
+            .await
+            .expect("Failed to set up test database");
+        let config = Config {
+            database_url: "sqlite::memory:".to_string(),
+            api_url: server.uri(),
+            fixture_mode: FixtureMode::Live,
+            http_addr: None,
+            api_key: None,
+        };
+        let ctx = AppContext::with_store(Arc::new(store), config);
+
+        // Anything not mounted explicitly is an empty last page
+        Mock::given(method("POST"))
+            .and(path("/search"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(job_response(Vec::new())))
+            .with_priority(u8::MAX)
+            .mount(&server)
+            .await;
+
+        Self { server, ctx }
+    }
+
+    pub async fn page(&self, source: &str, start_index: usize, ads: Vec<Value>) {
+        Mock::given(method("POST"))
+            .and(path("/search"))
+            .and(body_partial_json(json!({
+                "source": source,
+                "startIndex": start_index,
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(job_response(ads)))
+            .mount(&self.server)
+            .await;
+    }
+
+    pub async fn error(&self, source: &str, start_index: usize, status: u16) {
+        Mock::given(method("POST"))
+            .and(path("/search"))
+            .and(body_partial_json(json!({
+                "source": source,
+                "startIndex": start_index,
+            })))
+            .respond_with(ResponseTemplate::new(status).set_body_string("Internal error"))
+            .mount(&self.server)
+            .await;
+    }
+
+    pub async fn details(&self, id: &str, details: Value) {
+        Mock::given(method("GET"))
+            .and(path(format!("/job/{}", id)))
+            .respond_with(ResponseTemplate::new(200).set_body_json(details))
+            .mount(&self.server)
+            .await;
+    }
+
+    pub async fn received_requests(&self) -> usize {
+        self.server
+            .received_requests()
+            .await
+            .map(|requests| requests.len())
+            .unwrap_or_default()
+    }
+}
+
+pub fn job_response(ads: Vec<Value>) -> Value {
+    let number_of_ads = ads.len();
+    json!({
+        "ads": ads,
+        "numberOfAds": number_of_ads,
+    })
+}
+
+pub fn ad(id: &str, title: &str) -> Value {
+    json!({
+        "id": id,
+        "title": title,
+        "occupation": "Mjukvaruutvecklare",
+        "workplace": "Stockholm",
+        "workplaceName": "Testbolaget AB",
+        "publishedDate": "2026-10-18T08:00:00.000Z",
+        "lastApplicationDate": "2026-11-18T00:00:00.000Z",
+    })
+}
+
+pub fn external_ad(id: &str, title: &str, url: &str) -> Value {
+    let mut ad = ad(id, title);
+    ad["sourceLinks"] = json!([{ "label": "Extern annons", "url": url }]);
+    ad
+}
@@ -0,0 +1,55 @@
+//! Swedish municipalities (kommuner) and their parent regions.
+use crate::find_jobs::Region;
+use crate::taxonomy::{taxonomy, ConceptType};
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+static MUNICIPALITIES: OnceLock<Vec<Municipality>> = OnceLock::new();
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Municipality {
+    pub id: String,
+    pub label: String,
+    pub code: String,
+    pub region: Region,
+}
+
+pub fn municipalities() -> &'static [Municipality] {
+    MUNICIPALITIES.get_or_init(|| {
+        let taxonomy = taxonomy();
+        let mut municipalities = taxonomy
+            .of_type(ConceptType::Municipality)
+            .filter_map(|concept| {
+                let region = Region::from_concept_id(concept.parent.as_deref()?)?;
+                Some(Municipality {
+                    id: concept.id.clone(),
+                    label: concept.label.clone(),
+                    code: concept.code.clone().unwrap_or_default(),
+                    region,
+                })
+            })
+            .collect::<Vec<_>>();
+        municipalities.sort_by(|a, b| a.code.cmp(&b.code));
+        municipalities
+    })
+}
+
+pub fn find_municipality(label: &str) -> Option<&'static Municipality> {
+    let label = label.trim().to_lowercase();
+    municipalities()
+        .iter()
+        .find(|municipality| municipality.label.to_lowercase() == label)
+}
+
+pub fn find_municipality_by_id(id: &str) -> Option<&'static Municipality> {
+    municipalities()
+        .iter()
+        .find(|municipality| municipality.id == id)
+}
+
+pub fn municipalities_in(region: &Region) -> Vec<&'static Municipality> {
+    municipalities()
+        .iter()
+        .filter(|municipality| &municipality.region == region)
+        .collect()
+}
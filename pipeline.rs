@@ -0,0 +1,113 @@
+//! Streams scraped ads to the database while the search is still running.
+use crate::constants::{INSERT_BATCH_SIZE, PIPELINE_CAPACITY};
+use crate::context::AppContext;
+use crate::find_jobs::JobAd;
+use crate::metrics;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SinkStats {
+    pub received: u64,
+    pub inserted: u64,
+    pub updated: u64,
+    pub batches: u64,
+    pub write_time: Duration,
+}
+
+/// Hands pages of normalized ads to a writer task that upserts them in batches of
+/// [`INSERT_BATCH_SIZE`]. At most [`PIPELINE_CAPACITY`] pages wait in the channel, so a slow
+/// database holds back the fetching instead of growing memory.
+pub struct JobSink {
+    sender: mpsc::Sender<Vec<JobAd>>,
+    writer: JoinHandle<Result<SinkStats, sqlx::Error>>,
+}
+
+impl JobSink {
+    pub fn spawn(ctx: &AppContext) -> Self {
+        let (sender, receiver) = mpsc::channel(PIPELINE_CAPACITY);
+        let writer = tokio::spawn(write_batches(ctx.clone(), receiver));
+        Self { sender, writer }
+    }
+
+    /// Waits for room in the channel, fails if the writer has stopped on a database error.
+    pub async fn send(&self, jobs: Vec<JobAd>) -> Result<(), Box<dyn std::error::Error>> {
+        self.sender
+            .send(jobs)
+            .await
+            .map_err(|_| "Job writer stopped before the scrape finished".into())
+    }
+
+    /// Closes the channel and waits until the buffered ads are written.
+    pub async fn finish(self) -> Result<SinkStats, Box<dyn std::error::Error + Send + Sync>> {
+        drop(self.sender);
+        Ok(self.writer.await??)
+    }
+}
+
+async fn write_batches(
+    ctx: AppContext,
+    mut receiver: mpsc::Receiver<Vec<JobAd>>,
+) -> Result<SinkStats, sqlx::Error> {
+    let mut stats = SinkStats::default();
+    let mut batch: Vec<JobAd> = Vec::with_capacity(INSERT_BATCH_SIZE);
+
+    while let Some(jobs) = receiver.recv().await {
+        stats.received += jobs.len() as u64;
+        batch.extend(jobs);
+        if batch.len() >= INSERT_BATCH_SIZE {
+            write_batch(&ctx, &batch, &mut stats).await?;
+            batch.clear();
+        }
+    }
+    if !batch.is_empty() {
+        write_batch(&ctx, &batch, &mut stats).await?;
+    }
+    Ok(stats)
+}
+
+async fn write_batch(
+    ctx: &AppContext,
+    batch: &[JobAd],
+    stats: &mut SinkStats,
+) -> Result<(), sqlx::Error> {
+    let started = Instant::now();
+    let written = ctx.store.upsert_jobs(batch).await?;
+    stats.inserted += written.inserted;
+    stats.updated += written.updated;
+    stats.batches += 1;
+    stats.write_time += started.elapsed();
+    metrics::DB_WRITE_LATENCY.observe(started.elapsed().as_secs_f64());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::{context, job};
+
+    #[tokio::test]
+    async fn sink_writes_full_batches_and_the_remainder() {
+        let ctx = context().await;
+        let sink = JobSink::spawn(&ctx);
+        let total = INSERT_BATCH_SIZE + 10;
+        let ids = (0..total)
+            .map(|i| format!("sink-{}", i))
+            .collect::<Vec<_>>();
+        for page in ids.chunks(100) {
+            sink.send(page.iter().map(|id| job(id).build()).collect())
+                .await
+                .unwrap();
+        }
+        // Ads already stored are updated instead of inserted again
+        sink.send(vec![job("sink-0").build()]).await.unwrap();
+
+        let stats = sink.finish().await.unwrap();
+        assert_eq!(stats.received, total as u64 + 1);
+        assert_eq!(stats.inserted, total as u64);
+        assert_eq!(stats.updated, 1);
+        assert_eq!(stats.batches, 2);
+        assert_eq!(ctx.store.count_jobs().await.unwrap(), total as i64);
+    }
+}
@@ -0,0 +1,547 @@
+//! Salary of an ad, parsed from the free-text salary type and description of the ad details,
+//! such as "Fast månadslön" and "30 000 - 40 000 kr", and monthly salary statistics.
+use crate::context::AppContext;
+use crate::database::SalaryRow;
+use crate::stats::{dimension_key, Dimension, DimensionColumns, OTHER};
+use chrono::{Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Write;
+use strum_macros::{Display, EnumString};
+
+// Working hours in a month, used to compare hourly wages with monthly salaries
+const HOURS_PER_MONTH: i64 = 165;
+pub const DEFAULT_PERIOD_DAYS: i64 = 90;
+pub const DEFAULT_TOP: usize = 20;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Display, EnumString, Serialize, Deserialize)]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum SalaryType {
+    Fixed,
+    /// Commission, piece rate or other pay that depends on results.
+    Variable,
+    FixedAndVariable,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Display, EnumString, Serialize, Deserialize)]
+#[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum SalaryPeriod {
+    Hour,
+    Week,
+    Month,
+    Year,
+}
+
+impl SalaryPeriod {
+    /// None when the amount is too large to convert.
+    pub fn monthly(&self, amount: i64) -> Option<i64> {
+        match self {
+            SalaryPeriod::Hour => amount.checked_mul(HOURS_PER_MONTH),
+            SalaryPeriod::Week => amount.checked_mul(52).map(|amount| amount / 12),
+            SalaryPeriod::Month => Some(amount),
+            SalaryPeriod::Year => Some(amount / 12),
+        }
+    }
+
+    // The period named in the text, None when it names none or several
+    fn from_text(text: &str) -> Option<SalaryPeriod> {
+        let keywords: [(SalaryPeriod, &[&str]); 4] = [
+            (SalaryPeriod::Hour, &["tim", "/h", "per h"]),
+            (SalaryPeriod::Week, &["vecka", "veckolön", "/v"]),
+            (SalaryPeriod::Month, &["mån", "/m"]),
+            (SalaryPeriod::Year, &["årslön", "per år", "/år"]),
+        ];
+        let mut found = keywords
+            .into_iter()
+            .filter(|(_, words)| words.iter().any(|word| text.contains(word)))
+            .map(|(period, _)| period);
+        match (found.next(), found.next()) {
+            (Some(period), None) => Some(period),
+            _ => None,
+        }
+    }
+
+    // Typical Swedish pay, for amounts given without a period
+    fn from_amount(amount: i64) -> SalaryPeriod {
+        match amount {
+            ..=1_000 => SalaryPeriod::Hour,
+            1_001..=200_000 => SalaryPeriod::Month,
+            _ => SalaryPeriod::Year,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct Salary {
+    pub salary_type: Option<SalaryType>,
+    pub min: Option<i64>,
+    pub max: Option<i64>,
+    pub period: Option<SalaryPeriod>,
+    /// The salary type and description as published, joined with ", ".
+    pub description: Option<String>,
+}
+
+impl Salary {
+    pub fn parse(salary_type: Option<&str>, description: Option<&str>) -> Self {
+        let text = [salary_type, description]
+            .into_iter()
+            .flatten()
+            .map(str::trim)
+            .filter(|text| !text.is_empty())
+            .collect::<Vec<_>>()
+            .join(", ");
+        if text.is_empty() {
+            return Salary::default();
+        }
+        let lower = text.to_lowercase();
+
+        // Numbers only count as amounts next to a currency, "40 timmar" is not a salary
+        let amounts = amounts(&lower);
+        let min = amounts.iter().copied().min();
+        let max = amounts.iter().copied().max();
+        // The description names the period of the amount more precisely than the type, which
+        // often lists several
+        let period = description
+            .and_then(|description| SalaryPeriod::from_text(&description.to_lowercase()))
+            .or_else(|| SalaryPeriod::from_text(&lower))
+            .or_else(|| max.map(SalaryPeriod::from_amount));
+
+        Salary {
+            salary_type: salary_type_of(&lower),
+            min,
+            max,
+            period: min.and(period),
+            description: Some(text),
+        }
+    }
+
+    /// The upper end of the range in kronor per month, used by the minimum salary filter.
+    pub fn monthly(&self) -> Option<i64> {
+        self.period?.monthly(self.max?)
+    }
+}
+
+fn salary_type_of(text: &str) -> Option<SalaryType> {
+    let fixed = text.contains("fast");
+    let variable = ["rörlig", "provision", "ackord", "bonus"]
+        .iter()
+        .any(|word| text.contains(word));
+    match (fixed, variable) {
+        (true, true) => Some(SalaryType::FixedAndVariable),
+        (true, false) => Some(SalaryType::Fixed),
+        (false, true) => Some(SalaryType::Variable),
+        (false, false) => None,
+    }
+}
+
+fn is_group_separator(c: char) -> bool {
+    matches!(c, ' ' | '.' | '\u{a0}' | '\u{202f}')
+}
+
+// A number in a salary text and whether a currency is written next to it
+struct Number {
+    amount: i64,
+    thousands: bool,
+    currency: bool,
+    start: usize,
+    end: usize,
+}
+
+fn first_word(text: &str) -> &str {
+    text.split(|c: char| !c.is_alphanumeric())
+        .next()
+        .unwrap_or_default()
+}
+
+fn last_word(text: &str) -> &str {
+    text.rsplit(|c: char| !c.is_alphanumeric())
+        .next()
+        .unwrap_or_default()
+}
+
+/// Whole kronor in the text. Only numbers next to a currency, or in a range with one, are
+/// amounts, so the year of "Tillträde 2026, 35 000 kr" is not. "30 000", "30.000" and "30000"
+/// are the same amount, decimals such as ",50" are dropped and "30k" or "30 tkr" mean
+/// thousands, also for the "30" of "30-40 tkr". Amounts too large for an `i64` are dropped.
+fn amounts(text: &str) -> Vec<i64> {
+    let chars = text.chars().collect::<Vec<_>>();
+    let digit_at = |index: usize| chars.get(index).is_some_and(|c| c.is_ascii_digit());
+    let mut numbers = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        if !digit_at(i) || (i > 0 && chars[i - 1].is_alphanumeric()) {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        let mut digits = String::new();
+        while digit_at(i) {
+            digits.push(chars[i]);
+            i += 1;
+        }
+        // Groups of exactly three digits after a thousands separator
+        while i < chars.len()
+            && is_group_separator(chars[i])
+            && (1..=3).all(|offset| digit_at(i + offset))
+            && !digit_at(i + 4)
+        {
+            digits.extend(&chars[i + 1..i + 4]);
+            i += 4;
+        }
+        if chars.get(i) == Some(&',') && digit_at(i + 1) {
+            i += 1;
+            while digit_at(i) {
+                i += 1;
+            }
+        }
+
+        let before = chars[..start].iter().collect::<String>();
+        let rest = chars[i..].iter().collect::<String>();
+        let rest = rest.trim_start();
+        let unit = first_word(rest);
+        let thousands = matches!(unit, "tkr" | "k");
+        let currency = thousands
+            || rest.starts_with(":-")
+            || matches!(unit, "kr" | "sek" | "kronor")
+            || matches!(last_word(before.trim_end()), "kr" | "sek");
+        if let Ok(amount) = digits.parse::<i64>() {
+            numbers.push(Number {
+                amount,
+                thousands,
+                currency,
+                start,
+                end: i,
+            });
+        }
+    }
+
+    // Both ends of "30 000 - 40 000 kr" are amounts
+    for index in 1..numbers.len() {
+        let between = chars[numbers[index - 1].end..numbers[index].start]
+            .iter()
+            .collect::<String>();
+        if matches!(between.trim(), "-" | "–" | "—" | "till" | "och") {
+            let currency = numbers[index - 1].currency || numbers[index].currency;
+            numbers[index - 1].currency = currency;
+            numbers[index].currency = currency;
+        }
+    }
+
+    let numbers = numbers
+        .into_iter()
+        .filter(|number| number.currency)
+        .collect::<Vec<_>>();
+    let in_thousands = numbers.iter().any(|number| number.thousands);
+    numbers
+        .into_iter()
+        .filter_map(|number| {
+            if number.thousands || (in_thousands && number.amount < 1000) {
+                number.amount.checked_mul(1000)
+            } else {
+                Some(number.amount)
+            }
+        })
+        .collect()
+}
+
+/// What to group salaries by, also the query string of `GET /stats/salaries`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct SalaryQuery {
+    pub by: Dimension,
+    /// Only ads created in the last `days` days.
+    pub days: i64,
+    /// Number of keys with the most salaries to list.
+    pub top: usize,
+}
+
+impl Default for SalaryQuery {
+    fn default() -> Self {
+        Self {
+            by: Dimension::default(),
+            days: DEFAULT_PERIOD_DAYS,
+            top: DEFAULT_TOP,
+        }
+    }
+}
+
+const STATS_COLUMNS: [&str; 7] = ["ads", "min", "p25", "median", "p75", "max", "mean"];
+
+/// Monthly salaries in kronor of the ads with a parsed salary for one key.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct SalaryStats {
+    pub key: String,
+    pub ads: usize,
+    pub min: i64,
+    pub p25: i64,
+    pub median: i64,
+    pub p75: i64,
+    pub max: i64,
+    pub mean: i64,
+}
+
+impl SalaryStats {
+    fn new(key: String, mut salaries: Vec<i64>) -> Option<Self> {
+        if salaries.is_empty() {
+            return None;
+        }
+        salaries.sort_unstable();
+        // Nearest rank, the smallest salary with at least `percent` of the salaries at or below it
+        let percentile = |percent: usize| {
+            let rank = (percent * salaries.len()).div_ceil(100).max(1);
+            salaries[rank - 1]
+        };
+        Some(Self {
+            key,
+            ads: salaries.len(),
+            min: salaries[0],
+            p25: percentile(25),
+            median: percentile(50),
+            p75: percentile(75),
+            max: salaries[salaries.len() - 1],
+            mean: salaries.iter().sum::<i64>() / salaries.len() as i64,
+        })
+    }
+
+    fn values(&self) -> [String; 7] {
+        [
+            self.ads as i64,
+            self.min,
+            self.p25,
+            self.median,
+            self.p75,
+            self.max,
+            self.mean,
+        ]
+        .map(|value| value.to_string())
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SalaryReport {
+    pub by: Dimension,
+    pub since: String,
+    /// All ads with a salary, including the keys outside the top list.
+    pub total: Option<SalaryStats>,
+    pub groups: Vec<SalaryStats>,
+}
+
+impl DimensionColumns for SalaryRow {
+    fn occupation(&self) -> Option<&str> {
+        self.occupation.as_deref()
+    }
+
+    fn workplace(&self) -> Option<&str> {
+        self.workplace.as_deref()
+    }
+
+    fn source(&self) -> Option<&str> {
+        self.source.as_deref()
+    }
+
+    fn employer(&self) -> Option<&str> {
+        self.employer.as_deref()
+    }
+}
+
+pub fn build_salary_report(rows: &[SalaryRow], query: &SalaryQuery, since: &str) -> SalaryReport {
+    let mut salaries: HashMap<String, Vec<i64>> = HashMap::new();
+    for row in rows {
+        let key = dimension_key(query.by, row).unwrap_or_else(|| OTHER.to_string());
+        salaries.entry(key).or_default().push(row.salary_monthly);
+    }
+
+    let total = rows.iter().map(|row| row.salary_monthly).collect();
+    let mut groups = salaries
+        .into_iter()
+        .filter_map(|(key, salaries)| SalaryStats::new(key, salaries))
+        .collect::<Vec<_>>();
+    groups.sort_by(|a, b| b.ads.cmp(&a.ads).then_with(|| a.key.cmp(&b.key)));
+    groups.truncate(query.top);
+
+    SalaryReport {
+        by: query.by,
+        since: since.to_string(),
+        total: SalaryStats::new("Totalt".to_string(), total),
+        groups,
+    }
+}
+
+/// Salary statistics of the ads created in the last `query.days` days.
+pub async fn salary_report(
+    ctx: &AppContext,
+    query: &SalaryQuery,
+) -> Result<SalaryReport, sqlx::Error> {
+    let since = (Utc::now() - Duration::days(query.days.max(0)))
+        .format("%Y-%m-%dT%H:%M:%S.%3fZ")
+        .to_string();
+    let rows = ctx.store.salaries(&since).await?;
+    Ok(build_salary_report(&rows, query, &since))
+}
+
+impl SalaryReport {
+    fn rows(&self) -> impl Iterator<Item = &SalaryStats> {
+        self.groups.iter().chain(self.total.iter())
+    }
+
+    /// One row per key and a final row with all ads, salaries in kronor per month.
+    pub fn to_table(&self) -> String {
+        let key_width = self
+            .rows()
+            .map(|stats| stats.key.chars().count())
+            .max()
+            .unwrap_or_default()
+            .clamp(3, 40);
+        let mut table = format!(
+            "{:<width$}",
+            self.by.to_string().to_uppercase(),
+            width = key_width
+        );
+        for column in STATS_COLUMNS {
+            table.push_str(&format!("  {:>8}", column.to_uppercase()));
+        }
+        table.push('\n');
+
+        for stats in self.rows() {
+            let key = stats.key.chars().take(key_width).collect::<String>();
+            table.push_str(&format!("{:<width$}", key, width = key_width));
+            for value in stats.values() {
+                table.push_str(&format!("  {:>8}", value));
+            }
+            table.push('\n');
+        }
+        table
+    }
+
+    pub fn write_csv<W: Write>(&self, output: W) -> csv::Result<()> {
+        let mut writer = csv::Writer::from_writer(output);
+        let mut header = vec![self.by.to_string()];
+        header.extend(STATS_COLUMNS.map(str::to_string));
+        writer.write_record(&header)?;
+
+        for stats in self.rows() {
+            let mut record = vec![stats.key.clone()];
+            record.extend(stats.values());
+            writer.write_record(&record)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_a_monthly_range() {
+        let salary = Salary::parse(Some("Fast månadslön"), Some("30 000 - 40 000 kr"));
+        assert_eq!(salary.salary_type, Some(SalaryType::Fixed));
+        assert_eq!(salary.min, Some(30_000));
+        assert_eq!(salary.max, Some(40_000));
+        assert_eq!(salary.period, Some(SalaryPeriod::Month));
+        assert_eq!(salary.monthly(), Some(40_000));
+        assert_eq!(
+            salary.description.as_deref(),
+            Some("Fast månadslön, 30 000 - 40 000 kr")
+        );
+    }
+
+    #[test]
+    fn parses_hourly_wages_and_separators() {
+        let salary = Salary::parse(
+            Some("Fast månads- vecko- eller timlön"),
+            Some("Från 145,50 kr/tim"),
+        );
+        assert_eq!(salary.min, Some(145));
+        assert_eq!(salary.period, Some(SalaryPeriod::Hour));
+        assert_eq!(salary.monthly(), Some(145 * HOURS_PER_MONTH));
+
+        let salary = Salary::parse(None, Some("35.000:- i månaden plus provision"));
+        assert_eq!(salary.salary_type, Some(SalaryType::Variable));
+        assert_eq!(salary.max, Some(35_000));
+        assert_eq!(salary.period, Some(SalaryPeriod::Month));
+
+        let salary = Salary::parse(
+            Some("Rörlig ackords- eller provisionslön"),
+            Some("30-40 tkr"),
+        );
+        assert_eq!((salary.min, salary.max), (Some(30_000), Some(40_000)));
+    }
+
+    #[test]
+    fn text_without_amounts_keeps_the_type_only() {
+        let salary = Salary::parse(Some("Fast och rörlig lön"), Some("Enligt avtal, 40 timmar"));
+        assert_eq!(salary.salary_type, Some(SalaryType::FixedAndVariable));
+        assert_eq!(salary.min, None);
+        assert_eq!(salary.period, None);
+        assert_eq!(salary.monthly(), None);
+        assert_eq!(Salary::parse(None, Some("  ")), Salary::default());
+    }
+
+    #[test]
+    fn numbers_away_from_the_currency_are_not_amounts() {
+        let salary = Salary::parse(
+            Some("Fast månadslön"),
+            Some("Tillträde 2026, lön 35 000 kr i månaden"),
+        );
+        assert_eq!((salary.min, salary.max), (Some(35_000), Some(35_000)));
+
+        let salary = Salary::parse(
+            None,
+            Some("Mellan 30 000 och 38 000 kr, 2 veckors provanställning"),
+        );
+        assert_eq!((salary.min, salary.max), (Some(30_000), Some(38_000)));
+
+        let salary = Salary::parse(None, Some("SEK 50 000 per månad för 3 kunder"));
+        assert_eq!((salary.min, salary.max), (Some(50_000), Some(50_000)));
+    }
+
+    #[test]
+    fn amounts_too_large_to_convert_are_dropped() {
+        let salary = Salary::parse(None, Some("9223372036854775807 k"));
+        assert_eq!(salary.max, None);
+
+        let salary = Salary::parse(None, Some("9223372036854775807 kr/tim"));
+        assert_eq!(salary.max, Some(i64::MAX));
+        assert_eq!(salary.monthly(), None);
+    }
+
+    #[test]
+    fn salary_statistics_per_source() {
+        let row = |source: &str, salary: i64| SalaryRow {
+            source: Some(source.to_string()),
+            salary_monthly: salary,
+            ..Default::default()
+        };
+        let rows = [
+            row("pb", 30_000),
+            row("pb", 40_000),
+            row("pb", 50_000),
+            row("pb", 60_000),
+            row("external", 45_000),
+        ];
+        let query = SalaryQuery {
+            by: Dimension::Source,
+            ..Default::default()
+        };
+        let report = build_salary_report(&rows, &query, "2026-07-20T00:00:00.000Z");
+
+        let pb = &report.groups[0];
+        assert_eq!(pb.key, "pb");
+        assert_eq!((pb.ads, pb.min, pb.max), (4, 30_000, 60_000));
+        assert_eq!((pb.p25, pb.median, pb.p75), (30_000, 40_000, 50_000));
+        assert_eq!(pb.mean, 45_000);
+        assert_eq!(report.groups[1].median, 45_000);
+        assert_eq!(report.total.as_ref().unwrap().ads, 5);
+
+        let mut csv = Vec::new();
+        report.write_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert!(csv.starts_with("source,ads,min,p25,median,p75,max,mean\n"));
+        assert!(csv.contains("Totalt,5,30000,40000,45000,50000,60000,45000"));
+    }
+}
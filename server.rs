@@ -0,0 +1,65 @@
+//! HTTP endpoints served next to the scraper and the bot.
+use crate::context::AppContext;
+use crate::logging::{error, info};
+use crate::{api, health, metrics, web};
+use axum::extract::State;
+use axum::http::header::CONTENT_TYPE;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::{Json, Router};
+use std::net::SocketAddr;
+
+pub fn router(ctx: AppContext) -> Router {
+    Router::new()
+        .route("/metrics", get(render_metrics))
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .merge(api::routes(&ctx))
+        .merge(web::routes(&ctx))
+        .with_state(ctx)
+}
+
+/// Serves [`router`] on `addr` until the process exits.
+pub async fn serve(ctx: AppContext, addr: SocketAddr) -> std::io::Result<()> {
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    info!("Serving HTTP endpoints on {}", addr);
+    axum::serve(listener, router(ctx)).await
+}
+
+/// Starts [`serve`] in the background when `HTTP_ADDR` is configured.
+pub fn spawn(ctx: &AppContext) {
+    let Some(addr) = ctx.config.http_addr else {
+        return;
+    };
+    let ctx = ctx.clone();
+    tokio::spawn(async move {
+        if let Err(e) = serve(ctx, addr).await {
+            error!("HTTP server on {} stopped: {:?}", addr, e);
+        }
+    });
+}
+
+async fn render_metrics() -> impl IntoResponse {
+    (
+        [(CONTENT_TYPE, "text/plain; version=0.0.4")],
+        metrics::render(),
+    )
+}
+
+async fn healthz(State(ctx): State<AppContext>) -> impl IntoResponse {
+    health_response(health::liveness(&ctx).await)
+}
+
+async fn readyz(State(ctx): State<AppContext>) -> impl IntoResponse {
+    health_response(health::readiness(&ctx).await)
+}
+
+fn health_response(report: health::Report) -> impl IntoResponse {
+    let status = if report.ok {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(report))
+}
@@ -0,0 +1,616 @@
+//! Skills and technologies asked for in ads, matched against the title and description with a
+//! dictionary of Swedish and English terms, and reports of how often they are asked for over
+//! time and per region.
+use crate::constants::SKILLS_PATH;
+use crate::context::AppContext;
+use crate::database::{DailyCount, JobSkill, SearchQuery, SkillCount};
+use crate::find_jobs::Region;
+use crate::logging::{error, info};
+use crate::stats::{
+    bucket, dimension_key, series_table, write_series_csv, Dimension, DimensionColumns, Interval,
+    Series, OTHER,
+};
+use chrono::{Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::OnceLock;
+
+pub const DEFAULT_PERIODS: u32 = 12;
+pub const DEFAULT_TOP: usize = 20;
+pub const DEFAULT_REGION_PERIOD_DAYS: i64 = 30;
+pub const DEFAULT_REGION_TOP: usize = 10;
+
+static DICTIONARY: OnceLock<SkillDictionary> = OnceLock::new();
+
+// Skill and the terms that mention it, used when there is no dictionary at SKILLS_PATH
+const DEFAULT_SKILLS: &[(&str, &[&str])] = &[
+    ("Rust", &["rust"]),
+    ("Python", &["python"]),
+    ("Java", &["java"]),
+    ("JavaScript", &["javascript", "ecmascript"]),
+    ("TypeScript", &["typescript"]),
+    ("C#", &["c#", "csharp"]),
+    ("C++", &["c++", "cpp"]),
+    // Not named Go, which is a common Swedish word as in "ett go gäng"
+    ("Golang", &["golang"]),
+    ("Kotlin", &["kotlin"]),
+    ("Swift", &["swift"]),
+    ("PHP", &["php"]),
+    ("Ruby", &["ruby", "ruby on rails"]),
+    ("Scala", &["scala"]),
+    (".NET", &[".net", "dotnet", "asp.net"]),
+    ("Node.js", &["node.js", "nodejs"]),
+    ("React", &["react", "reactjs", "react.js", "react native"]),
+    ("Angular", &["angular"]),
+    ("Vue", &["vue", "vue.js", "vuejs"]),
+    ("SQL", &["sql", "t-sql"]),
+    ("PostgreSQL", &["postgresql", "postgres"]),
+    ("Docker", &["docker"]),
+    ("Kubernetes", &["kubernetes", "k8s", "openshift"]),
+    ("AWS", &["aws", "amazon web services"]),
+    ("Azure", &["azure"]),
+    ("GCP", &["gcp", "google cloud"]),
+    ("Terraform", &["terraform"]),
+    ("Linux", &["linux"]),
+    ("Git", &["git", "github", "gitlab"]),
+    (
+        "CI/CD",
+        &[
+            "ci/cd",
+            "ci-cd",
+            "continuous integration",
+            "kontinuerlig integration",
+        ],
+    ),
+    (
+        "Maskininlärning",
+        &["maskininlärning", "machine learning", "deep learning"],
+    ),
+    (
+        "AI",
+        &["ai", "artificiell intelligens", "artificial intelligence"],
+    ),
+    (
+        "Agila metoder",
+        &["agil", "agila", "agilt", "agile", "scrum", "kanban"],
+    ),
+    (
+        "Testautomatisering",
+        &[
+            "testautomatisering",
+            "test automation",
+            "automatiserade tester",
+        ],
+    ),
+    ("Inbyggda system", &["inbyggda system", "embedded"]),
+    ("SAP", &["sap"]),
+    ("Excel", &["excel"]),
+    ("Power BI", &["power bi", "powerbi"]),
+    ("Salesforce", &["salesforce"]),
+];
+
+// Heads of Swedish compounds a term may be joined with, as in "javautvecklare" or
+// "pythonkunskaper", also used by keyword watches
+pub(crate) const COMPOUND_HEADS: [&str; 6] = [
+    "utvecklare",
+    "programmerare",
+    "kunskap",
+    "erfarenhet",
+    "miljö",
+    "baserad",
+];
+
+/// A skill and the terms that mention it, the entries of `data/skills.json`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SkillEntry {
+    pub skill: String,
+    /// Synonyms in any language, the skill name itself always matches.
+    #[serde(default)]
+    pub terms: Vec<String>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct SkillDictionary {
+    entries: Vec<SkillEntry>,
+}
+
+impl SkillDictionary {
+    pub fn new(entries: Vec<SkillEntry>) -> Self {
+        let entries = entries
+            .into_iter()
+            .map(|entry| {
+                let mut terms = std::iter::once(&entry.skill)
+                    .chain(&entry.terms)
+                    .map(|term| term.trim().to_lowercase())
+                    .filter(|term| !term.is_empty())
+                    .collect::<Vec<_>>();
+                terms.sort();
+                terms.dedup();
+                SkillEntry {
+                    skill: entry.skill,
+                    terms,
+                }
+            })
+            .collect();
+        Self { entries }
+    }
+
+    pub fn builtin() -> Self {
+        Self::new(
+            DEFAULT_SKILLS
+                .iter()
+                .map(|(skill, terms)| SkillEntry {
+                    skill: skill.to_string(),
+                    terms: terms.iter().map(|term| term.to_string()).collect(),
+                })
+                .collect(),
+        )
+    }
+
+    /// Skills mentioned in the text, in dictionary order.
+    pub fn extract(&self, text: &str) -> Vec<String> {
+        let text = text.to_lowercase();
+        self.entries
+            .iter()
+            .filter(|entry| entry.terms.iter().any(|term| mentions(&text, term)))
+            .map(|entry| entry.skill.clone())
+            .collect()
+    }
+
+    /// Skills of an ad, mentioned in its title or description.
+    pub fn job_skills(
+        &self,
+        id: &str,
+        title: Option<&str>,
+        description: Option<&str>,
+    ) -> Vec<JobSkill> {
+        let text = format!(
+            "{}\n{}",
+            title.unwrap_or_default(),
+            description.unwrap_or_default()
+        );
+        self.extract(&text)
+            .into_iter()
+            .map(|skill| JobSkill {
+                job_id: id.to_string(),
+                skill,
+            })
+            .collect()
+    }
+}
+
+fn mentions(text: &str, term: &str) -> bool {
+    mentions_word(text, term, &COMPOUND_HEADS)
+}
+
+/// The term as a whole word, or as the first part of a Swedish compound with one of `heads`.
+pub(crate) fn mentions_word(text: &str, term: &str, heads: &[&str]) -> bool {
+    text.match_indices(term).any(|(start, _)| {
+        let before = text[..start].chars().next_back();
+        let rest = &text[start + term.len()..];
+        !before.is_some_and(char::is_alphanumeric)
+            && (!rest.chars().next().is_some_and(char::is_alphanumeric)
+                || heads.iter().any(|head| rest.starts_with(head)))
+    })
+}
+
+/// The dictionary in [`SKILLS_PATH`], or the built-in one when there is no such file.
+pub fn dictionary() -> &'static SkillDictionary {
+    DICTIONARY.get_or_init(|| match load_dictionary() {
+        Ok(Some(dictionary)) => {
+            info!("Loaded skill dictionary from {}", SKILLS_PATH);
+            dictionary
+        }
+        Ok(None) => SkillDictionary::builtin(),
+        Err(e) => {
+            error!(
+                "Failed to load skill dictionary from {}: {:?}",
+                SKILLS_PATH, e
+            );
+            SkillDictionary::builtin()
+        }
+    })
+}
+
+fn load_dictionary() -> Result<Option<SkillDictionary>, Box<dyn std::error::Error>> {
+    let content = match std::fs::read_to_string(SKILLS_PATH) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let entries: Vec<SkillEntry> = serde_json::from_str(&content)?;
+    Ok(Some(SkillDictionary::new(entries)))
+}
+
+/// Extracts the skills of every stored ad again, after the dictionary has changed. Returns the
+/// number of skills stored.
+pub async fn reextract_skills(ctx: &AppContext) -> Result<usize, sqlx::Error> {
+    let dictionary = dictionary();
+    let jobs = ctx.store.query_jobs(SearchQuery::All).await?;
+    let skills = jobs
+        .iter()
+        .filter_map(|job| {
+            let id = job.id.as_deref()?;
+            Some(dictionary.job_skills(id, job.title.as_deref(), job.description.as_deref()))
+        })
+        .flatten()
+        .collect::<Vec<_>>();
+    ctx.store.replace_job_skills(&skills).await?;
+    info!("Extracted {} skills from {} ads", skills.len(), jobs.len());
+    Ok(skills.len())
+}
+
+impl DimensionColumns for SkillCount {
+    fn workplace(&self) -> Option<&str> {
+        self.workplace.as_deref()
+    }
+}
+
+fn region_of(row: &impl DimensionColumns) -> String {
+    dimension_key(Dimension::Region, row).unwrap_or_else(|| OTHER.to_string())
+}
+
+/// What to count, also the query string of `GET /stats/skills`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct SkillQuery {
+    pub interval: Interval,
+    /// Number of periods up to and including the current one.
+    pub periods: u32,
+    /// Number of skills asked for in the most ads to list.
+    pub top: usize,
+    /// Only ads with a workplace in the region.
+    pub region: Option<Region>,
+}
+
+impl Default for SkillQuery {
+    fn default() -> Self {
+        Self {
+            interval: Interval::Week,
+            periods: DEFAULT_PERIODS,
+            top: DEFAULT_TOP,
+            region: None,
+        }
+    }
+}
+
+/// New ads asking for each skill per period.
+#[derive(Clone, Debug, Serialize)]
+pub struct SkillTrends {
+    pub interval: Interval,
+    pub region: Option<Region>,
+    /// Period labels, `YYYY-MM-DD` for days and `YYYY-Www` for weeks, oldest first.
+    pub periods: Vec<String>,
+    pub skills: Vec<Series>,
+}
+
+pub fn build_skill_trends(
+    rows: &[SkillCount],
+    query: &SkillQuery,
+    today: NaiveDate,
+) -> SkillTrends {
+    let region = query.region.as_ref().map(|region| format!("{:?}", region));
+    let rows = rows
+        .iter()
+        .filter(|row| {
+            region
+                .as_ref()
+                .is_none_or(|region| region_of(*row) == *region)
+        })
+        .map(|row| (row.day.as_str(), Some(row.skill.clone()), row.count));
+    let buckets = bucket(rows, query.interval, query.periods, query.top, today);
+    SkillTrends {
+        interval: query.interval,
+        region: query.region.clone(),
+        periods: buckets.periods,
+        skills: buckets.series,
+    }
+}
+
+fn days_before(today: NaiveDate, days: i64) -> String {
+    (today - Duration::days(days.max(0)))
+        .format("%Y-%m-%d")
+        .to_string()
+}
+
+/// Skill trends of the ads stored in the database, up to today in UTC.
+pub async fn skill_trends(
+    ctx: &AppContext,
+    query: &SkillQuery,
+) -> Result<SkillTrends, sqlx::Error> {
+    let today = Utc::now().date_naive();
+    // Weeks start on Monday, so look back far enough to cover the first one
+    let days = match query.interval {
+        Interval::Day => query.periods as i64,
+        Interval::Week => query.periods as i64 * 7 + 7,
+    };
+    let rows = ctx.store.skill_counts(&days_before(today, days)).await?;
+    Ok(build_skill_trends(&rows, query, today))
+}
+
+impl SkillTrends {
+    /// One row per skill, one column per period.
+    pub fn to_table(&self) -> String {
+        series_table("skill", &self.periods, self.skills.iter())
+    }
+
+    pub fn write_csv<W: Write>(&self, output: W) -> csv::Result<()> {
+        write_series_csv("skill", &self.periods, self.skills.iter(), output)
+    }
+}
+
+/// Period of the skills per region, also the query string of `GET /stats/skills/regions`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct RegionSkillQuery {
+    /// Only ads created in the last `days` days.
+    pub days: i64,
+    /// Number of skills to list per region.
+    pub top: usize,
+}
+
+impl Default for RegionSkillQuery {
+    fn default() -> Self {
+        Self {
+            days: DEFAULT_REGION_PERIOD_DAYS,
+            top: DEFAULT_REGION_TOP,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct SkillShare {
+    pub skill: String,
+    pub ads: i64,
+    /// Share of the ads in the region asking for the skill.
+    pub percent: f64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct RegionSkills {
+    pub region: String,
+    pub ads: i64,
+    pub skills: Vec<SkillShare>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct RegionSkillReport {
+    pub since: String,
+    /// Regions with the most ads first.
+    pub regions: Vec<RegionSkills>,
+}
+
+/// The skills asked for in the most ads of each region, shares are of all ads in the region.
+pub fn build_region_skills(
+    ads: &[DailyCount],
+    skills: &[SkillCount],
+    query: &RegionSkillQuery,
+    since: &str,
+) -> RegionSkillReport {
+    let mut ads_per_region: HashMap<String, i64> = HashMap::new();
+    for row in ads {
+        *ads_per_region.entry(region_of(row)).or_default() += row.count;
+    }
+    let mut skill_ads: HashMap<String, HashMap<String, i64>> = HashMap::new();
+    for row in skills {
+        *skill_ads
+            .entry(region_of(row))
+            .or_default()
+            .entry(row.skill.clone())
+            .or_default() += row.count;
+    }
+
+    let mut regions = ads_per_region
+        .into_iter()
+        .map(|(region, ads)| {
+            let mut skills = skill_ads
+                .remove(&region)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(skill, count)| SkillShare {
+                    skill,
+                    ads: count,
+                    percent: count as f64 * 100.0 / ads.max(1) as f64,
+                })
+                .collect::<Vec<_>>();
+            skills.sort_by(|a, b| b.ads.cmp(&a.ads).then_with(|| a.skill.cmp(&b.skill)));
+            skills.truncate(query.top);
+            RegionSkills {
+                region,
+                ads,
+                skills,
+            }
+        })
+        .collect::<Vec<_>>();
+    regions.sort_by(|a, b| b.ads.cmp(&a.ads).then_with(|| a.region.cmp(&b.region)));
+
+    RegionSkillReport {
+        since: since.to_string(),
+        regions,
+    }
+}
+
+/// Skills per region of the ads created in the last `query.days` days.
+pub async fn region_skills(
+    ctx: &AppContext,
+    query: &RegionSkillQuery,
+) -> Result<RegionSkillReport, sqlx::Error> {
+    let since = days_before(Utc::now().date_naive(), query.days);
+    let ads = ctx.store.daily_counts(&since).await?;
+    let skills = ctx.store.skill_counts(&since).await?;
+    Ok(build_region_skills(&ads, &skills, query, &since))
+}
+
+impl RegionSkillReport {
+    /// One row per region with its top skills and their share of the ads.
+    pub fn to_table(&self) -> String {
+        let region_width = self
+            .regions
+            .iter()
+            .map(|region| region.region.chars().count())
+            .max()
+            .unwrap_or_default()
+            .clamp(6, 30);
+        let mut table = format!(
+            "{:<width$}  {:>6}  {}\n",
+            "REGION",
+            "ADS",
+            "SKILLS",
+            width = region_width
+        );
+        for region in &self.regions {
+            let skills = region
+                .skills
+                .iter()
+                .map(|share| format!("{} {} ({:.0}%)", share.skill, share.ads, share.percent))
+                .collect::<Vec<_>>()
+                .join(", ");
+            table.push_str(&format!(
+                "{:<width$}  {:>6}  {}\n",
+                region.region,
+                region.ads,
+                skills,
+                width = region_width
+            ));
+        }
+        table
+    }
+
+    /// One row per region and skill.
+    pub fn write_csv<W: Write>(&self, output: W) -> csv::Result<()> {
+        let mut writer = csv::Writer::from_writer(output);
+        writer.write_record(["region", "region_ads", "skill", "ads", "percent"])?;
+        for region in &self.regions {
+            for share in &region.skills {
+                writer.write_record([
+                    region.region.clone(),
+                    region.ads.to_string(),
+                    share.skill.clone(),
+                    share.ads.to_string(),
+                    format!("{:.1}", share.percent),
+                ])?;
+            }
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::{context, job};
+
+    #[test]
+    fn matches_whole_terms_synonyms_and_compounds() {
+        let dictionary = SkillDictionary::builtin();
+        let skills = dictionary.extract(
+            "Vi söker en Javautvecklare med erfarenhet av Kubernetes (k8s), C# och CI/CD. \
+             Meriterande: JavaScript, maskininlärning och agilt arbetssätt.",
+        );
+        assert_eq!(
+            skills,
+            vec![
+                "Java",
+                "JavaScript",
+                "C#",
+                "Kubernetes",
+                "CI/CD",
+                "Maskininlärning",
+                "Agila metoder"
+            ]
+        );
+        // Parts of words do not count
+        assert!(dictionary
+            .extract("Rustik miljö, gott om saplingar")
+            .is_empty());
+        // Nor common words that are the name of a language
+        assert!(dictionary
+            .extract("Du får ett go gäng som kollegor")
+            .is_empty());
+        assert_eq!(dictionary.extract("Golang-utvecklare"), vec!["Golang"]);
+    }
+
+    #[test]
+    fn custom_dictionary_lowercases_terms_and_includes_the_name() {
+        let dictionary = SkillDictionary::new(vec![SkillEntry {
+            skill: "Elixir".to_string(),
+            terms: vec!["Phoenix Framework".to_string()],
+        }]);
+        let skills = dictionary.job_skills("1", Some("Elixir-utvecklare"), None);
+        assert_eq!(skills.len(), 1);
+        assert_eq!(skills[0].skill, "Elixir");
+        assert_eq!(dictionary.extract("PHOENIX FRAMEWORK"), vec!["Elixir"]);
+    }
+
+    #[test]
+    fn skill_shares_per_region() {
+        let count = |workplace: &str, count: i64| DailyCount {
+            day: "2026-10-18".to_string(),
+            workplace: Some(workplace.to_string()),
+            count,
+            ..Default::default()
+        };
+        let skill = |workplace: &str, skill: &str, count: i64| SkillCount {
+            day: "2026-10-18".to_string(),
+            skill: skill.to_string(),
+            workplace: Some(workplace.to_string()),
+            count,
+        };
+        let ads = [count("Malmö", 6), count("Lund", 4), count("Stockholm", 5)];
+        let skills = [
+            skill("Malmö", "Rust", 2),
+            skill("Lund", "Rust", 3),
+            skill("Lund", "Python", 1),
+            skill("Stockholm", "Java", 5),
+        ];
+        let report = build_region_skills(&ads, &skills, &RegionSkillQuery::default(), "2026-09-18");
+
+        assert_eq!(report.regions[0].region, "Skåne");
+        assert_eq!(report.regions[0].ads, 10);
+        assert_eq!(report.regions[0].skills[0].skill, "Rust");
+        assert_eq!(report.regions[0].skills[0].percent, 50.0);
+        assert_eq!(report.regions[1].skills[0].percent, 100.0);
+    }
+
+    #[tokio::test]
+    async fn stored_ads_get_skills_from_title_and_description() {
+        let ctx = context().await;
+        let today = Utc::now().format("%Y-%m-%dT%H:%M:%S.%3fZ").to_string();
+        ctx.store
+            .insert_jobs(&[
+                job("1")
+                    .title("Rustutvecklare")
+                    .workplace("Malmö")
+                    .description("Vi kör Kubernetes i AWS")
+                    .created_at(&today)
+                    .build(),
+                job("2")
+                    .title("Backendutvecklare Rust")
+                    .workplace("Lund")
+                    .created_at(&today)
+                    .build(),
+            ])
+            .await
+            .unwrap();
+
+        let trends = skill_trends(
+            &ctx,
+            &SkillQuery {
+                region: Some(Region::Skåne),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        let skills = trends
+            .skills
+            .iter()
+            .map(|series| (series.key.as_str(), series.total))
+            .collect::<Vec<_>>();
+        assert_eq!(skills, vec![("Rust", 2), ("AWS", 1), ("Kubernetes", 1)]);
+
+        assert_eq!(reextract_skills(&ctx).await.unwrap(), 4);
+    }
+}
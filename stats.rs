@@ -0,0 +1,480 @@
+//! Time series of new ads per day or week, broken down by occupation field, region, source or
+//! employer.
+use crate::context::AppContext;
+use crate::database::DailyCount;
+use crate::find_jobs::OccupationType;
+use crate::municipality::find_municipality;
+use chrono::{Datelike, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Write;
+use strum_macros::{Display, EnumString};
+
+pub const DEFAULT_PERIODS: u32 = 14;
+pub const MAX_PERIODS: u32 = 366;
+pub const DEFAULT_TOP: usize = 10;
+// Key of ads without a value for the dimension, as in the subscription digest
+pub(crate) const OTHER: &str = "Övrigt";
+
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Display, EnumString, Serialize, Deserialize,
+)]
+#[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum Dimension {
+    /// Occupation field, resolved from the occupation name through the taxonomy.
+    #[default]
+    Occupation,
+    /// Region of the workplace municipality.
+    Region,
+    Source,
+    Employer,
+}
+
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Display, EnumString, Serialize, Deserialize,
+)]
+#[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum Interval {
+    #[default]
+    Day,
+    /// ISO weeks starting on Monday.
+    Week,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Display, EnumString)]
+#[strum(serialize_all = "lowercase")]
+pub enum OutputFormat {
+    #[default]
+    Table,
+    Json,
+    Csv,
+}
+
+impl Interval {
+    fn period_start(&self, date: NaiveDate) -> NaiveDate {
+        match self {
+            Interval::Day => date,
+            Interval::Week => date - Duration::days(date.weekday().num_days_from_monday() as i64),
+        }
+    }
+
+    fn step(&self) -> Duration {
+        match self {
+            Interval::Day => Duration::days(1),
+            Interval::Week => Duration::weeks(1),
+        }
+    }
+
+    fn label(&self, start: NaiveDate) -> String {
+        match self {
+            Interval::Day => start.format("%Y-%m-%d").to_string(),
+            Interval::Week => {
+                let week = start.iso_week();
+                format!("{}-W{:02}", week.year(), week.week())
+            }
+        }
+    }
+}
+
+/// What to count, also the query string of `GET /stats/trends`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct TrendQuery {
+    pub by: Dimension,
+    pub interval: Interval,
+    /// Number of periods up to and including the current one.
+    pub periods: u32,
+    /// Number of keys with the most ads to list.
+    pub top: usize,
+}
+
+impl Default for TrendQuery {
+    fn default() -> Self {
+        Self {
+            by: Dimension::default(),
+            interval: Interval::default(),
+            periods: DEFAULT_PERIODS,
+            top: DEFAULT_TOP,
+        }
+    }
+}
+
+/// New ads per period for one key. The change compares the last period, which is still
+/// running, with the one before it.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Series {
+    pub key: String,
+    pub counts: Vec<i64>,
+    pub total: i64,
+    pub change: i64,
+    /// `None` when the previous period had no ads.
+    pub change_percent: Option<f64>,
+}
+
+impl Series {
+    fn new(key: String, counts: Vec<i64>) -> Self {
+        let total = counts.iter().sum();
+        let current = counts.last().copied().unwrap_or_default();
+        let previous = counts.iter().rev().nth(1).copied().unwrap_or_default();
+        let change = current - previous;
+        Self {
+            key,
+            counts,
+            total,
+            change,
+            change_percent: (previous > 0).then(|| change as f64 * 100.0 / previous as f64),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Trends {
+    pub by: Dimension,
+    pub interval: Interval,
+    /// Period labels, `YYYY-MM-DD` for days and `YYYY-Www` for weeks, oldest first.
+    pub periods: Vec<String>,
+    /// All ads, including the keys outside the top list.
+    pub total: Series,
+    pub series: Vec<Series>,
+}
+
+/// Ad columns a [`Dimension`] is resolved from, rows without a column leave it out.
+pub(crate) trait DimensionColumns {
+    fn occupation(&self) -> Option<&str> {
+        None
+    }
+
+    fn workplace(&self) -> Option<&str> {
+        None
+    }
+
+    fn source(&self) -> Option<&str> {
+        None
+    }
+
+    fn employer(&self) -> Option<&str> {
+        None
+    }
+}
+
+impl DimensionColumns for DailyCount {
+    fn occupation(&self) -> Option<&str> {
+        self.occupation.as_deref()
+    }
+
+    fn workplace(&self) -> Option<&str> {
+        self.workplace.as_deref()
+    }
+
+    fn source(&self) -> Option<&str> {
+        self.source.as_deref()
+    }
+
+    fn employer(&self) -> Option<&str> {
+        self.employer.as_deref()
+    }
+}
+
+pub(crate) fn dimension_key(by: Dimension, row: &impl DimensionColumns) -> Option<String> {
+    match by {
+        Dimension::Occupation => row
+            .occupation()
+            .and_then(OccupationType::from_occupation_name)
+            .map(|occupation| occupation.taxonomy_label().to_string()),
+        Dimension::Region => row
+            .workplace()
+            .and_then(find_municipality)
+            .map(|municipality| format!("{:?}", municipality.region)),
+        Dimension::Source => row.source().map(str::to_string),
+        Dimension::Employer => row.employer().map(str::to_string),
+    }
+}
+
+fn first_period(interval: Interval, periods: u32, today: NaiveDate) -> NaiveDate {
+    let periods = periods.clamp(1, MAX_PERIODS) as i32;
+    interval.period_start(today) - interval.step() * (periods - 1)
+}
+
+/// Counts per key of the periods ending with the one holding `today`, the `top` keys with the
+/// most counts first.
+pub(crate) struct Buckets {
+    /// Period labels, oldest first.
+    pub periods: Vec<String>,
+    /// All counts, including the keys outside the top list.
+    pub total: Series,
+    pub series: Vec<Series>,
+}
+
+/// Sums `(day, key, count)` rows into periods, rows without a key count as [`OTHER`] and rows
+/// outside the periods are skipped.
+pub(crate) fn bucket<'a>(
+    rows: impl IntoIterator<Item = (&'a str, Option<String>, i64)>,
+    interval: Interval,
+    periods: u32,
+    top: usize,
+    today: NaiveDate,
+) -> Buckets {
+    let first = first_period(interval, periods, today);
+    let periods = periods.clamp(1, MAX_PERIODS) as usize;
+    let starts = (0..periods)
+        .map(|index| first + interval.step() * index as i32)
+        .collect::<Vec<_>>();
+    let index_of = starts
+        .iter()
+        .enumerate()
+        .map(|(index, start)| (*start, index))
+        .collect::<HashMap<_, _>>();
+
+    let mut counts: HashMap<String, Vec<i64>> = HashMap::new();
+    for (day, key, count) in rows {
+        let Ok(day) = NaiveDate::parse_from_str(day, "%Y-%m-%d") else {
+            continue;
+        };
+        let Some(&index) = index_of.get(&interval.period_start(day)) else {
+            continue;
+        };
+        let key = key.unwrap_or_else(|| OTHER.to_string());
+        counts.entry(key).or_insert_with(|| vec![0; periods])[index] += count;
+    }
+
+    let mut totals = vec![0; periods];
+    for series in counts.values() {
+        for (total, count) in totals.iter_mut().zip(series) {
+            *total += count;
+        }
+    }
+    let mut series = counts
+        .into_iter()
+        .map(|(key, counts)| Series::new(key, counts))
+        .collect::<Vec<_>>();
+    series.sort_by(|a, b| b.total.cmp(&a.total).then_with(|| a.key.cmp(&b.key)));
+    series.truncate(top);
+
+    Buckets {
+        periods: starts.iter().map(|start| interval.label(*start)).collect(),
+        total: Series::new("Totalt".to_string(), totals),
+        series,
+    }
+}
+
+/// Buckets the daily counts into the periods of `query` that end with the one holding `today`.
+pub fn build_trends(rows: &[DailyCount], query: &TrendQuery, today: NaiveDate) -> Trends {
+    let rows = rows
+        .iter()
+        .map(|row| (row.day.as_str(), dimension_key(query.by, row), row.count));
+    let buckets = bucket(rows, query.interval, query.periods, query.top, today);
+    Trends {
+        by: query.by,
+        interval: query.interval,
+        periods: buckets.periods,
+        total: buckets.total,
+        series: buckets.series,
+    }
+}
+
+/// Trends of the ads stored in the database, up to today in UTC.
+pub async fn trends(ctx: &AppContext, query: &TrendQuery) -> Result<Trends, sqlx::Error> {
+    let today = Utc::now().date_naive();
+    let created_after = first_period(query.interval, query.periods, today)
+        .format("%Y-%m-%d")
+        .to_string();
+    let rows = ctx.store.daily_counts(&created_after).await?;
+    Ok(build_trends(&rows, query, today))
+}
+
+fn format_change(series: &Series) -> String {
+    match series.change_percent {
+        Some(percent) => format!("{:+} ({:+.1}%)", series.change, percent),
+        None => format!("{:+}", series.change),
+    }
+}
+
+/// One row per series, one column per period, then the total and the change.
+pub(crate) fn series_table<'a>(
+    heading: &str,
+    periods: &[String],
+    rows: impl Iterator<Item = &'a Series> + Clone,
+) -> String {
+    let key_width = rows
+        .clone()
+        .map(|series| series.key.chars().count())
+        .max()
+        .unwrap_or_default()
+        .clamp(3, 40);
+    let mut table = format!("{:<width$}", heading.to_uppercase(), width = key_width);
+    for period in periods {
+        table.push_str(&format!("  {:>10}", period));
+    }
+    table.push_str(&format!("  {:>8}  {}\n", "TOTAL", "CHANGE"));
+
+    for series in rows {
+        let key = series.key.chars().take(key_width).collect::<String>();
+        table.push_str(&format!("{:<width$}", key, width = key_width));
+        for count in &series.counts {
+            table.push_str(&format!("  {:>10}", count));
+        }
+        table.push_str(&format!(
+            "  {:>8}  {}\n",
+            series.total,
+            format_change(series)
+        ));
+    }
+    table
+}
+
+/// Same layout as [`series_table`], the change percentage is empty when unknown.
+pub(crate) fn write_series_csv<'a, W: Write>(
+    heading: &str,
+    periods: &[String],
+    rows: impl Iterator<Item = &'a Series>,
+    output: W,
+) -> csv::Result<()> {
+    let mut writer = csv::Writer::from_writer(output);
+    let mut header = vec![heading.to_string()];
+    header.extend(periods.iter().cloned());
+    header.extend(["total", "change", "change_percent"].map(str::to_string));
+    writer.write_record(&header)?;
+
+    for series in rows {
+        let mut record = vec![series.key.clone()];
+        record.extend(series.counts.iter().map(i64::to_string));
+        record.push(series.total.to_string());
+        record.push(series.change.to_string());
+        record.push(
+            series
+                .change_percent
+                .map(|percent| format!("{:.1}", percent))
+                .unwrap_or_default(),
+        );
+        writer.write_record(&record)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+impl Trends {
+    fn rows(&self) -> impl Iterator<Item = &Series> + Clone {
+        self.series.iter().chain(std::iter::once(&self.total))
+    }
+
+    /// One row per key and a final row with all ads, one column per period.
+    pub fn to_table(&self) -> String {
+        series_table(&self.by.to_string(), &self.periods, self.rows())
+    }
+
+    /// Same layout as [`Trends::to_table`], the change percentage is empty when unknown.
+    pub fn write_csv<W: Write>(&self, output: W) -> csv::Result<()> {
+        write_series_csv(&self.by.to_string(), &self.periods, self.rows(), output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::{context, job};
+
+    fn row(day: &str, source: &str, employer: &str, count: i64) -> DailyCount {
+        DailyCount {
+            day: day.to_string(),
+            source: Some(source.to_string()),
+            employer: Some(employer.to_string()),
+            count,
+            ..Default::default()
+        }
+    }
+
+    fn date(text: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(text, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn days_are_bucketed_into_iso_weeks() {
+        let rows = [
+            // Monday and Sunday of week 41, Tuesday of week 42 and a day before the range
+            row("2026-10-05", "pb", "Alfa AB", 2),
+            row("2026-10-11", "pb", "Alfa AB", 3),
+            row("2026-10-13", "external", "Beta AB", 10),
+            row("2026-09-01", "pb", "Alfa AB", 100),
+        ];
+        let query = TrendQuery {
+            by: Dimension::Source,
+            interval: Interval::Week,
+            periods: 2,
+            ..Default::default()
+        };
+        let trends = build_trends(&rows, &query, date("2026-10-14"));
+
+        assert_eq!(trends.periods, vec!["2026-W41", "2026-W42"]);
+        assert_eq!(trends.series[0].key, "external");
+        assert_eq!(trends.series[0].counts, vec![0, 10]);
+        assert_eq!(trends.series[0].change_percent, None);
+        assert_eq!(trends.series[1].counts, vec![5, 0]);
+        assert_eq!(trends.series[1].change_percent, Some(-100.0));
+        assert_eq!(trends.total.counts, vec![5, 10]);
+        assert_eq!(trends.total.change, 5);
+    }
+
+    #[test]
+    fn top_keys_are_kept_and_the_total_covers_all() {
+        let rows = [
+            row("2026-10-17", "pb", "Alfa AB", 1),
+            row("2026-10-18", "pb", "Beta AB", 4),
+            row("2026-10-18", "pb", "Gamma AB", 2),
+        ];
+        let query = TrendQuery {
+            by: Dimension::Employer,
+            periods: 2,
+            top: 2,
+            ..Default::default()
+        };
+        let trends = build_trends(&rows, &query, date("2026-10-18"));
+
+        let keys = trends
+            .series
+            .iter()
+            .map(|series| series.key.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(keys, vec!["Beta AB", "Gamma AB"]);
+        assert_eq!(trends.total.total, 7);
+
+        let mut csv = Vec::new();
+        trends.write_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert_eq!(
+            csv.lines().next(),
+            Some("employer,2026-10-17,2026-10-18,total,change,change_percent")
+        );
+        assert!(csv.contains("Totalt,1,6,7,5,500.0"));
+    }
+
+    #[tokio::test]
+    async fn trends_count_stored_ads_by_region() {
+        let ctx = context().await;
+        let today = Utc::now().format("%Y-%m-%dT%H:%M:%S.%3fZ").to_string();
+        ctx.store
+            .insert_jobs(&[
+                job("1").workplace("Malmö").created_at(&today).build(),
+                job("2").workplace("Lund").created_at(&today).build(),
+                job("3").workplace("Okänd ort").created_at(&today).build(),
+                job("4")
+                    .workplace("Malmö")
+                    .created_at("2020-01-01T08:00:00.000Z")
+                    .build(),
+            ])
+            .await
+            .unwrap();
+
+        let query = TrendQuery {
+            by: Dimension::Region,
+            ..Default::default()
+        };
+        let trends = trends(&ctx, &query).await.unwrap();
+        assert_eq!(trends.periods.len(), DEFAULT_PERIODS as usize);
+        assert_eq!(trends.series[0].key, "Skåne");
+        assert_eq!(trends.series[0].total, 2);
+        assert_eq!(trends.series[1].key, OTHER);
+        assert_eq!(trends.total.total, 3);
+    }
+}
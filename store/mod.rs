@@ -0,0 +1,605 @@
+//! Storage backends for job ads and bot users.
+use crate::bot::UserSelections;
+use crate::database::{
+    DailyCount, DbJobAd, EmployerAd, GroupCount, JobField, JobListing, JobPage, JobSkill,
+    SalaryRow, ScrapeRun, SearchQuery, SkillCount, Subscriber, User, WrittenAds,
+};
+use crate::find_jobs::JobAd;
+use crate::language::Language;
+use crate::municipality::{find_municipality, find_municipality_by_id};
+use crate::salary::Salary;
+use crate::skills;
+use crate::subscription::DeliveryFrequency;
+use crate::work_mode::{classify_ad, WorkMode};
+use async_trait::async_trait;
+use chrono::Utc;
+use sqlx::{Database, Encode, QueryBuilder, Type};
+use std::collections::HashSet;
+use std::sync::Arc;
+
+pub mod postgres;
+pub mod sqlite;
+
+pub use postgres::PostgresStore;
+pub use sqlite::SqliteStore;
+
+/// Storage for scraped ads, bot users and their watches.
+#[async_trait]
+pub trait JobStore: Send + Sync {
+    /// Inserts new ads and keeps the stored fields of ads already stored, only their
+    /// `last_seen_at` moves to the time of the batch. Returns the number of new rows. Writing
+    /// ads, new or stored, also adds the skills mentioned in them, see [`crate::skills`]. Ads
+    /// without an id are skipped and of an ad listed twice the first copy is written.
+    async fn insert_jobs(&self, jobs: &[JobAd]) -> Result<u64, sqlx::Error>;
+    /// Inserts ads or updates the stored copy, a stored description is kept when the ad has
+    /// none. Of an ad listed twice the last copy is written.
+    async fn upsert_jobs(&self, jobs: &[JobAd]) -> Result<WrittenAds, sqlx::Error>;
+    async fn query_jobs(&self, query: SearchQuery) -> Result<Vec<DbJobAd>, sqlx::Error>;
+    async fn jobs_created_after(&self, created_after: &str) -> Result<Vec<DbJobAd>, sqlx::Error>;
+    async fn count_jobs(&self) -> Result<i64, sqlx::Error>;
+    async fn get_job(&self, id: &str) -> Result<Option<DbJobAd>, sqlx::Error>;
+    /// One sorted page of the ads matching every filter of the listing, text filters match
+    /// case-insensitively.
+    async fn list_jobs(&self, listing: &JobListing) -> Result<JobPage, sqlx::Error>;
+    /// Number of ads per value of `field`, most common first.
+    async fn count_jobs_by(
+        &self,
+        field: JobField,
+        limit: i64,
+    ) -> Result<Vec<GroupCount>, sqlx::Error>;
+    /// Ads created on or after `created_after`, counted per day, occupation, workplace, source
+    /// and employer.
+    async fn daily_counts(&self, created_after: &str) -> Result<Vec<DailyCount>, sqlx::Error>;
+    /// Ads with an employer created on or after `created_after` or still open on `open_on`,
+    /// oldest first.
+    async fn employer_ads(
+        &self,
+        created_after: &str,
+        open_on: &str,
+    ) -> Result<Vec<EmployerAd>, sqlx::Error>;
+    /// Ads whose details have not been fetched and failed fewer than
+    /// [`crate::constants::MAX_DETAILS_ATTEMPTS`] times, newest first and ads that failed least first.
+    async fn job_ids_without_details(&self, limit: i64) -> Result<Vec<String>, sqlx::Error>;
+    /// Marks the details of the ad as fetched, also when they have no description.
+    async fn mark_details_fetched(&self, id: &str, fetched_at: &str) -> Result<(), sqlx::Error>;
+    /// Counts a failed fetch of the details of the ad.
+    async fn record_details_failure(&self, id: &str) -> Result<(), sqlx::Error>;
+    async fn update_description(&self, id: &str, description: &str) -> Result<(), sqlx::Error>;
+    async fn update_language(
+        &self,
+        id: &str,
+        language: Option<Language>,
+    ) -> Result<(), sqlx::Error>;
+    async fn update_work_mode(
+        &self,
+        id: &str,
+        work_mode: Option<WorkMode>,
+    ) -> Result<(), sqlx::Error>;
+    /// Stores the parsed salary, an empty description marks ads whose details have no salary.
+    async fn update_salary(&self, id: &str, salary: &Salary) -> Result<(), sqlx::Error>;
+    /// Ads with a monthly salary created on or after `created_after`.
+    async fn salaries(&self, created_after: &str) -> Result<Vec<SalaryRow>, sqlx::Error>;
+    /// Adds skills of stored ads, skills already stored are kept. Returns the number of new
+    /// rows.
+    async fn add_job_skills(&self, skills: &[JobSkill]) -> Result<u64, sqlx::Error>;
+    /// Replaces the skills of every ad.
+    async fn replace_job_skills(&self, skills: &[JobSkill]) -> Result<(), sqlx::Error>;
+    /// Ads created on or after `created_after` counted per day, skill and workplace.
+    async fn skill_counts(&self, created_after: &str) -> Result<Vec<SkillCount>, sqlx::Error>;
+
+    async fn store_user(&self, user: User) -> Result<(), sqlx::Error>;
+    async fn get_user(&self, user_id: &str) -> Result<Option<User>, sqlx::Error>;
+    async fn store_subscription(
+        &self,
+        user_id: &str,
+        frequency: DeliveryFrequency,
+        delivery_hour: Option<u32>,
+    ) -> Result<(), sqlx::Error>;
+    async fn get_subscribers(&self) -> Result<Vec<Subscriber>, sqlx::Error>;
+    async fn mark_delivered(&self, user_id: &str, delivered_at: &str) -> Result<(), sqlx::Error>;
+
+    /// Records the start of a scrape and returns the id of the new run.
+    async fn start_scrape_run(&self, run: &ScrapeRun) -> Result<i64, sqlx::Error>;
+    /// Stores the end time, counters and error of a started run.
+    async fn finish_scrape_run(&self, run: &ScrapeRun) -> Result<(), sqlx::Error>;
+    /// The most recent runs, newest first.
+    async fn scrape_runs(&self, limit: i64) -> Result<Vec<ScrapeRun>, sqlx::Error>;
+    /// The latest run without errors of every source.
+    async fn last_successful_runs(&self) -> Result<Vec<ScrapeRun>, sqlx::Error>;
+
+    /// Checks that the database answers.
+    async fn ping(&self) -> Result<(), sqlx::Error>;
+}
+
+/// Opens the backend matching the scheme of `database_url` and applies its migrations.
+pub async fn connect(database_url: &str) -> Result<Arc<dyn JobStore>, sqlx::Error> {
+    if database_url.starts_with("postgres://") || database_url.starts_with("postgresql://") {
+        Ok(Arc::new(PostgresStore::connect(database_url).await?))
+    } else if database_url.starts_with("sqlite:") {
+        Ok(Arc::new(SqliteStore::connect(database_url).await?))
+    } else {
+        Err(sqlx::Error::Configuration(
+            format!("Unsupported database url {}", database_url).into(),
+        ))
+    }
+}
+
+// Selections are stored as one JSON column per kind, None leaves the stored value as is
+pub(crate) struct SelectionColumns {
+    pub occupations: Option<String>,
+    pub regions: Option<String>,
+    pub municipalities: Option<String>,
+    pub keyword_watches: Option<String>,
+}
+
+impl SelectionColumns {
+    pub fn from_selections(selections: UserSelections) -> Self {
+        Self {
+            occupations: selections
+                .selected_occupations
+                .map(|occupations| serde_json::to_string(&occupations).unwrap_or_default()),
+            regions: selections
+                .selected_regions
+                .map(|regions| serde_json::to_string(&regions).unwrap_or_default()),
+            municipalities: selections
+                .selected_municipalities
+                .map(|municipalities| serde_json::to_string(&municipalities).unwrap_or_default()),
+            keyword_watches: selections
+                .keyword_watches
+                .map(|watches| serde_json::to_string(&watches).unwrap_or_default()),
+        }
+    }
+
+    pub fn into_selections(self) -> UserSelections {
+        UserSelections {
+            selected_occupations: self.occupations.and_then(|o| serde_json::from_str(&o).ok()),
+            selected_regions: self.regions.and_then(|r| serde_json::from_str(&r).ok()),
+            selected_municipalities: self
+                .municipalities
+                .and_then(|m| serde_json::from_str(&m).ok()),
+            keyword_watches: self
+                .keyword_watches
+                .and_then(|k| serde_json::from_str(&k).ok()),
+        }
+    }
+}
+
+// Columns searched by the free text of a listing
+pub(crate) const TEXT_SEARCH_COLUMNS: [&str; 4] =
+    ["title", "workplace_name", "occupation", "description"];
+
+/// How a backend compares text filters without regard to case.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum TextMatch {
+    // LIKE and lower() in SQLite only fold ASCII letters, so Örebro would not match örebro.
+    // GLOB patterns list both cases of every letter instead.
+    Glob,
+    Ilike,
+}
+
+impl TextMatch {
+    fn operator(self) -> &'static str {
+        match self {
+            TextMatch::Glob => " GLOB ",
+            TextMatch::Ilike => " ILIKE ",
+        }
+    }
+
+    /// Pattern matching `value` as a literal, anywhere in the column unless `exact`.
+    pub(crate) fn pattern(self, value: &str, exact: bool) -> String {
+        let any = match self {
+            TextMatch::Glob => "*",
+            TextMatch::Ilike => "%",
+        };
+        let mut pattern = String::new();
+        if !exact {
+            pattern.push_str(any);
+        }
+        for c in value.chars() {
+            match self {
+                TextMatch::Glob => push_glob_char(&mut pattern, c),
+                TextMatch::Ilike => {
+                    if matches!(c, '%' | '_' | '\\') {
+                        pattern.push('\\');
+                    }
+                    pattern.push(c);
+                }
+            }
+        }
+        if !exact {
+            pattern.push_str(any);
+        }
+        pattern
+    }
+}
+
+fn push_glob_char(pattern: &mut String, c: char) {
+    let mut lower = c.to_lowercase();
+    let mut upper = c.to_uppercase();
+    match (lower.next(), lower.next(), upper.next(), upper.next()) {
+        (Some(lower), None, Some(upper), None) if lower != upper => {
+            pattern.extend(['[', lower, upper, ']']);
+        }
+        _ if matches!(c, '*' | '?' | '[') => pattern.extend(['[', c, ']']),
+        _ => pattern.push(c),
+    }
+}
+
+/// Column and values of a text filter, the values of a filter are alternatives.
+pub(crate) struct TextFilter {
+    pub column: &'static str,
+    pub values: Vec<String>,
+    /// Whether a value has to match the whole column rather than a part of it.
+    pub exact: bool,
+}
+
+// Text filter of a SearchQuery, None for the queries that are not matched against text
+pub(crate) fn text_filter(query: SearchQuery) -> Option<TextFilter> {
+    let (column, values, exact) = match query {
+        SearchQuery::Location(locations) => ("workplace", locations, false),
+        // Municipalities are resolved to their taxonomy name and match the whole workplace, so
+        // Lund does not match Lunds universitet in Uppsala
+        SearchQuery::Municipality(municipalities) => {
+            let names = municipalities
+                .into_iter()
+                .map(|value| {
+                    find_municipality_by_id(&value)
+                        .or_else(|| find_municipality(&value))
+                        .map_or(value, |municipality| municipality.label.clone())
+                })
+                .collect();
+            ("workplace", names, true)
+        }
+        SearchQuery::Title(titles) => ("title", titles, false),
+        SearchQuery::Occupation(occupations) => ("occupation", occupations, false),
+        SearchQuery::Company(companies) => ("workplace_name", companies, false),
+        SearchQuery::Source(sources) => ("source", sources, false),
+        // Language codes are matched exactly, "en" is not part of another code
+        SearchQuery::Language(languages) => ("language", languages, true),
+        SearchQuery::WorkMode(work_modes) => ("work_mode", work_modes, true),
+        SearchQuery::MostRecent(_) | SearchQuery::All => return None,
+    };
+    Some(TextFilter {
+        column,
+        values,
+        exact,
+    })
+}
+
+/// Pushes `separator` and the condition of `filter`, filters without a condition push nothing.
+/// Returns whether a condition was pushed.
+pub(crate) fn push_search_filter<'args, DB>(
+    query: &mut QueryBuilder<'args, DB>,
+    separator: &str,
+    filter: SearchQuery,
+    matching: TextMatch,
+) -> bool
+where
+    DB: Database,
+    String: Encode<'args, DB> + Type<DB>,
+{
+    if let SearchQuery::MostRecent(duration) = filter {
+        let created_after = Utc::now() - duration;
+        query.push(separator).push("created_at >= ");
+        query.push_bind(created_after.format("%Y-%m-%dT%H:%M:%S.%3fZ").to_string());
+        return true;
+    }
+    let Some(filter) = text_filter(filter) else {
+        return false;
+    };
+    if filter.values.is_empty() {
+        return false;
+    }
+    query.push(separator).push("(");
+    for (index, value) in filter.values.iter().enumerate() {
+        if index > 0 {
+            query.push(" OR ");
+        }
+        query.push(filter.column).push(matching.operator());
+        query.push_bind(matching.pattern(value, filter.exact));
+    }
+    query.push(")");
+    true
+}
+
+/// Pushes the WHERE clause of a listing. Filters are combined with AND and the values of a
+/// text filter with OR.
+pub(crate) fn push_listing_filters<'args, DB>(
+    query: &mut QueryBuilder<'args, DB>,
+    listing: &JobListing,
+    matching: TextMatch,
+) where
+    DB: Database,
+    String: Encode<'args, DB> + Type<DB>,
+    i64: Encode<'args, DB> + Type<DB>,
+{
+    let mut separator = " WHERE ";
+    for filter in listing.filters.iter().cloned() {
+        if push_search_filter(query, separator, filter, matching) {
+            separator = " AND ";
+        }
+    }
+    if let Some(created_after) = &listing.created_after {
+        query.push(separator).push("created_at >= ");
+        query.push_bind(created_after.clone());
+        separator = " AND ";
+    }
+    if let Some(created_before) = &listing.created_before {
+        query.push(separator).push("created_at <= ");
+        query.push_bind(created_before.clone());
+        separator = " AND ";
+    }
+    if let Some(min_salary) = listing.min_salary {
+        query.push(separator).push("salary_monthly >= ");
+        query.push_bind(min_salary);
+        separator = " AND ";
+    }
+    if let Some(text) = &listing.text {
+        query.push(separator).push("(");
+        for (index, column) in TEXT_SEARCH_COLUMNS.iter().enumerate() {
+            if index > 0 {
+                query.push(" OR ");
+            }
+            query.push(*column).push(matching.operator());
+            query.push_bind(matching.pattern(text, false));
+        }
+        query.push(")");
+    }
+}
+
+/// The count of the ads matching a listing and the query selecting its page.
+pub(crate) fn listing_queries<'args, DB>(
+    listing: &JobListing,
+    matching: TextMatch,
+) -> (QueryBuilder<'args, DB>, QueryBuilder<'args, DB>)
+where
+    DB: Database,
+    String: Encode<'args, DB> + Type<DB>,
+    i64: Encode<'args, DB> + Type<DB>,
+{
+    let mut count = QueryBuilder::new("SELECT COUNT(*) FROM jobs");
+    push_listing_filters(&mut count, listing, matching);
+
+    let mut page = QueryBuilder::new("SELECT * FROM jobs");
+    push_listing_filters(&mut page, listing, matching);
+    page.push(format!(
+        " ORDER BY {} {} NULLS LAST, id LIMIT ",
+        listing.sort.column(),
+        if listing.descending { "DESC" } else { "ASC" }
+    ));
+    page.push_bind(listing.limit);
+    page.push(" OFFSET ");
+    page.push_bind(listing.offset);
+    (count, page)
+}
+
+/// Multi-row INSERT of `jobs`, `on_conflict` decides what happens to ads already stored.
+pub(crate) fn insert_jobs_query<'args, DB>(
+    jobs: &[&'args JobAd],
+    on_conflict: OnConflict,
+) -> QueryBuilder<'args, DB>
+where
+    DB: Database,
+    Option<&'args str>: Encode<'args, DB> + Type<DB>,
+    Option<String>: Encode<'args, DB> + Type<DB>,
+{
+    let mut query = QueryBuilder::new(format!("INSERT INTO jobs ({}) ", JOB_COLUMNS));
+    query.push_values(jobs.iter().copied(), |mut row, job| {
+        row.push_bind(job.id.as_deref())
+            .push_bind(job.title.as_deref())
+            .push_bind(job.occupation.as_deref())
+            .push_bind(job.workplace.as_deref())
+            .push_bind(job.workplace_name.as_deref())
+            .push_bind(job.published_date.as_deref())
+            .push_bind(job.last_application_date.as_deref())
+            .push_bind(job.source.as_deref())
+            .push_bind(job.url.as_deref())
+            .push_bind(job.created_at.as_deref())
+            .push_bind(job.description.as_deref())
+            .push_bind(
+                Language::detect(job.title.as_deref(), job.description.as_deref())
+                    .map(|language| language.to_string()),
+            )
+            .push_bind(
+                classify_ad(
+                    job.workplace.as_deref(),
+                    job.title.as_deref(),
+                    job.description.as_deref(),
+                )
+                .map(|work_mode| work_mode.to_string()),
+            );
+    });
+    query.push(" ON CONFLICT (id) ").push(on_conflict.clause());
+    query
+}
+
+/// Counts the ads of `jobs` that are already stored, the ones an upsert updates.
+pub(crate) fn count_stored_query<'args, DB>(jobs: &[&'args JobAd]) -> QueryBuilder<'args, DB>
+where
+    DB: Database,
+    Option<&'args str>: Encode<'args, DB> + Type<DB>,
+{
+    let mut query = QueryBuilder::new("SELECT COUNT(*) FROM jobs WHERE id IN ");
+    push_ids(&mut query, jobs);
+    query
+}
+
+/// Stores `seen_at` as the time `jobs` were last seen in a scrape.
+pub(crate) fn mark_seen_query<'args, DB>(
+    seen_at: &'args str,
+    jobs: &[&'args JobAd],
+) -> QueryBuilder<'args, DB>
+where
+    DB: Database,
+    &'args str: Encode<'args, DB> + Type<DB>,
+    Option<&'args str>: Encode<'args, DB> + Type<DB>,
+{
+    let mut query = QueryBuilder::new("UPDATE jobs SET last_seen_at = ");
+    query.push_bind(seen_at).push(" WHERE id IN ");
+    push_ids(&mut query, jobs);
+    query
+}
+
+fn push_ids<'args, DB>(query: &mut QueryBuilder<'args, DB>, jobs: &[&'args JobAd])
+where
+    DB: Database,
+    Option<&'args str>: Encode<'args, DB> + Type<DB>,
+{
+    query.push("(");
+    let mut ids = query.separated(", ");
+    for job in jobs {
+        ids.push_bind(job.id.as_deref());
+    }
+    ids.push_unseparated(")");
+}
+
+/// Multi-row INSERT of `skills`, pairs already stored are skipped.
+pub(crate) fn insert_skills_query<'args, DB>(skills: &'args [JobSkill]) -> QueryBuilder<'args, DB>
+where
+    DB: Database,
+    &'args str: Encode<'args, DB> + Type<DB>,
+{
+    let mut query = QueryBuilder::new("INSERT INTO job_skills (job_id, skill) ");
+    query.push_values(skills, |mut row, skill| {
+        row.push_bind(skill.job_id.as_str())
+            .push_bind(skill.skill.as_str());
+    });
+    query.push(" ON CONFLICT (job_id, skill) DO NOTHING");
+    query
+}
+
+// Ads per value of `field`, largest groups first
+pub(crate) fn count_jobs_by_query(field: JobField) -> String {
+    format!(
+        "SELECT {} AS value, COUNT(*) AS count FROM jobs GROUP BY {} ORDER BY count DESC, value LIMIT $1",
+        field.column(),
+        field.column()
+    )
+}
+
+// Rows per multi-row INSERT that keep the bound parameters below the backend's limit
+pub(crate) fn rows_per_statement(max_variables: usize) -> usize {
+    max_variables / JOB_COLUMN_COUNT
+}
+
+// Scrape time of a batch, which normalized ads carry in `created_at`, stored as `last_seen_at`
+pub(crate) fn seen_at(jobs: &[&JobAd]) -> String {
+    jobs.iter()
+        .filter_map(|job| job.created_at.as_deref())
+        .max()
+        .map(str::to_string)
+        .unwrap_or_else(|| Utc::now().format("%Y-%m-%dT%H:%M:%S.%3fZ").to_string())
+}
+
+// Skills mentioned in the title and description of a batch of ads
+pub(crate) fn extract_skills(jobs: &[&JobAd]) -> Vec<JobSkill> {
+    let dictionary = skills::dictionary();
+    jobs.iter()
+        .filter_map(|job| {
+            let id = job.id.as_deref()?;
+            Some(dictionary.job_skills(id, job.title.as_deref(), job.description.as_deref()))
+        })
+        .flatten()
+        .collect()
+}
+
+/// What writing an ad that is already stored does.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum OnConflict {
+    /// The stored ad is left as is.
+    Skip,
+    /// The stored ad is updated, see [`UPDATE_STORED_JOB`].
+    Update,
+}
+
+impl OnConflict {
+    fn clause(self) -> &'static str {
+        match self {
+            OnConflict::Skip => "DO NOTHING",
+            OnConflict::Update => UPDATE_STORED_JOB,
+        }
+    }
+}
+
+// Ads without an id cannot be stored. An ad listed twice in a batch is written once: an insert
+// keeps the first copy as a later insert would, an upsert the last copy since PostgreSQL refuses
+// to update the same row twice in one statement.
+pub(crate) fn dedup_by_id(jobs: &[JobAd], on_conflict: OnConflict) -> Vec<&JobAd> {
+    let mut seen = HashSet::new();
+    let with_id = jobs.iter().filter(|job| job.id.is_some());
+    match on_conflict {
+        OnConflict::Skip => with_id
+            .filter(|&job| seen.insert(job.id.as_deref()))
+            .collect(),
+        OnConflict::Update => {
+            let mut unique = with_id
+                .rev()
+                .filter(|&job| seen.insert(job.id.as_deref()))
+                .collect::<Vec<_>>();
+            unique.reverse();
+            unique
+        }
+    }
+}
+
+pub(crate) const JOB_COLUMN_COUNT: usize = 13;
+pub(crate) const JOB_COLUMNS: &str = "id, title, occupation, workplace, workplace_name, published_date, last_application_date, source, url, created_at, description, language, work_mode";
+pub(crate) const SCRAPE_RUN_FINISH: &str = "UPDATE scrape_runs SET finished_at = $1, pages_fetched = $2, ads_received = $3, ads_available = $4, ads_inserted = $5, ads_updated = $6, error = $7 WHERE id = $8";
+pub(crate) const LAST_SUCCESSFUL_RUNS: &str = "SELECT * FROM scrape_runs WHERE id IN (SELECT MAX(id) FROM scrape_runs WHERE finished_at IS NOT NULL AND error IS NULL GROUP BY source) ORDER BY source";
+pub(crate) const DAILY_COUNTS: &str = "SELECT SUBSTR(created_at, 1, 10) AS day, occupation, workplace, source, workplace_name AS employer, COUNT(*) AS count FROM jobs WHERE created_at >= $1 GROUP BY SUBSTR(created_at, 1, 10), occupation, workplace, source, workplace_name";
+pub(crate) const EMPLOYER_ADS: &str = "SELECT id, workplace_name AS employer, title, occupation, workplace, created_at, last_seen_at, last_application_date FROM jobs WHERE workplace_name IS NOT NULL AND (created_at >= $1 OR last_application_date >= $2) ORDER BY created_at, id";
+pub(crate) const JOB_IDS_WITHOUT_DETAILS: &str = "SELECT id FROM jobs WHERE details_fetched_at IS NULL AND details_attempts < $1 ORDER BY details_attempts, created_at DESC LIMIT $2";
+pub(crate) const MARK_DETAILS_FETCHED: &str =
+    "UPDATE jobs SET details_fetched_at = $1 WHERE id = $2";
+pub(crate) const RECORD_DETAILS_FAILURE: &str =
+    "UPDATE jobs SET details_attempts = details_attempts + 1 WHERE id = $1";
+pub(crate) const UPDATE_SALARY: &str = "UPDATE jobs SET salary_type = $1, salary_description = $2, salary_min = $3, salary_max = $4, salary_period = $5, salary_monthly = $6 WHERE id = $7";
+pub(crate) const SALARIES: &str = "SELECT occupation, workplace, source, workplace_name AS employer, salary_monthly FROM jobs WHERE salary_monthly IS NOT NULL AND created_at >= $1";
+pub(crate) const SKILL_COUNTS: &str = "SELECT SUBSTR(jobs.created_at, 1, 10) AS day, job_skills.skill, jobs.workplace, COUNT(*) AS count FROM job_skills JOIN jobs ON jobs.id = job_skills.job_id WHERE jobs.created_at >= $1 GROUP BY SUBSTR(jobs.created_at, 1, 10), job_skills.skill, jobs.workplace";
+pub(crate) const UPDATE_STORED_JOB: &str = r#"DO UPDATE SET
+    title = EXCLUDED.title,
+    occupation = EXCLUDED.occupation,
+    workplace = EXCLUDED.workplace,
+    workplace_name = EXCLUDED.workplace_name,
+    published_date = EXCLUDED.published_date,
+    last_application_date = EXCLUDED.last_application_date,
+    source = EXCLUDED.source,
+    url = EXCLUDED.url,
+    description = COALESCE(EXCLUDED.description, jobs.description),
+    language = CASE WHEN EXCLUDED.description IS NULL AND jobs.description IS NOT NULL
+        THEN jobs.language ELSE EXCLUDED.language END,
+    work_mode = CASE WHEN EXCLUDED.description IS NULL AND jobs.description IS NOT NULL
+        THEN jobs.work_mode ELSE EXCLUDED.work_mode END"#;
+pub(crate) const JOBS_CREATED_AFTER: &str =
+    "SELECT * FROM jobs WHERE created_at > $1 ORDER BY occupation, published_date DESC";
+pub(crate) const COUNT_JOBS: &str = "SELECT COUNT(*) FROM jobs";
+pub(crate) const GET_JOB: &str = "SELECT * FROM jobs WHERE id = $1";
+pub(crate) const UPDATE_DESCRIPTION: &str = "UPDATE jobs SET description = $1 WHERE id = $2";
+pub(crate) const UPDATE_LANGUAGE: &str = "UPDATE jobs SET language = $1 WHERE id = $2";
+pub(crate) const UPDATE_WORK_MODE: &str = "UPDATE jobs SET work_mode = $1 WHERE id = $2";
+pub(crate) const STORE_USER: &str = r#"
+    INSERT INTO users (id, jobcategories, regions, municipalities, keyword_watches)
+    VALUES ($1, $2, $3, $4, $5)
+    ON CONFLICT (id)
+    DO UPDATE SET
+        jobcategories = CASE WHEN EXCLUDED.jobcategories IS NOT NULL THEN EXCLUDED.jobcategories ELSE users.jobcategories END,
+        regions = CASE WHEN EXCLUDED.regions IS NOT NULL THEN EXCLUDED.regions ELSE users.regions END,
+        municipalities = CASE WHEN EXCLUDED.municipalities IS NOT NULL THEN EXCLUDED.municipalities ELSE users.municipalities END,
+        keyword_watches = CASE WHEN EXCLUDED.keyword_watches IS NOT NULL THEN EXCLUDED.keyword_watches ELSE users.keyword_watches END
+    "#;
+pub(crate) const GET_USER: &str =
+    "SELECT id, jobcategories, regions, municipalities, keyword_watches FROM users WHERE id = $1";
+pub(crate) const STORE_SUBSCRIPTION: &str = r#"
+    INSERT INTO users (id, frequency, delivery_hour)
+    VALUES ($1, $2, $3)
+    ON CONFLICT (id)
+    DO UPDATE SET
+        frequency = EXCLUDED.frequency,
+        delivery_hour = EXCLUDED.delivery_hour
+    "#;
+pub(crate) const SUBSCRIBERS: &str = "SELECT id, jobcategories, regions, municipalities, keyword_watches, frequency, delivery_hour, last_delivered_at FROM users WHERE frequency IS NOT NULL";
+pub(crate) const MARK_DELIVERED: &str = "UPDATE users SET last_delivered_at = $1 WHERE id = $2";
+pub(crate) const SCRAPE_RUN_START: &str =
+    "INSERT INTO scrape_runs (started_at, source, filters) VALUES ($1, $2, $3) RETURNING id";
+pub(crate) const SCRAPE_RUNS: &str =
+    "SELECT * FROM scrape_runs ORDER BY started_at DESC, id DESC LIMIT $1";
@@ -0,0 +1,424 @@
+use super::{
+    count_jobs_by_query, count_stored_query, dedup_by_id, extract_skills, insert_jobs_query,
+    insert_skills_query, listing_queries, mark_seen_query, push_search_filter, rows_per_statement,
+    seen_at, JobStore, OnConflict, SelectionColumns, TextMatch, COUNT_JOBS, DAILY_COUNTS,
+    EMPLOYER_ADS, GET_JOB, GET_USER, JOBS_CREATED_AFTER, JOB_IDS_WITHOUT_DETAILS,
+    LAST_SUCCESSFUL_RUNS, MARK_DELIVERED, MARK_DETAILS_FETCHED, RECORD_DETAILS_FAILURE, SALARIES,
+    SCRAPE_RUNS, SCRAPE_RUN_FINISH, SCRAPE_RUN_START, SKILL_COUNTS, STORE_SUBSCRIPTION, STORE_USER,
+    SUBSCRIBERS, UPDATE_DESCRIPTION, UPDATE_LANGUAGE, UPDATE_SALARY, UPDATE_WORK_MODE,
+};
+use crate::constants::MAX_DETAILS_ATTEMPTS;
+use crate::database::{
+    DailyCount, DbJobAd, EmployerAd, GroupCount, JobField, JobListing, JobPage, JobSkill,
+    SalaryRow, ScrapeRun, SearchQuery, SkillCount, Subscriber, User, WrittenAds,
+};
+use crate::find_jobs::JobAd;
+use crate::language::Language;
+use crate::logging::{error, info};
+use crate::salary::Salary;
+use crate::subscription::DeliveryFrequency;
+use crate::work_mode::WorkMode;
+use async_trait::async_trait;
+use sqlx::postgres::{PgConnection, PgPool, PgRow};
+use sqlx::{Postgres, QueryBuilder, Row};
+use std::time::Instant;
+
+// The PostgreSQL protocol sends the number of bind parameters as a 16-bit integer
+const MAX_VARIABLES: usize = 65535;
+
+pub struct PostgresStore {
+    pool: PgPool,
+}
+
+impl PostgresStore {
+    pub async fn connect(database_url: &str) -> Result<Self, sqlx::Error> {
+        let pool = PgPool::connect(database_url).await?;
+        sqlx::migrate!("./migrations/postgres").run(&pool).await?;
+        Ok(Self { pool })
+    }
+
+    pub fn pool(&self) -> &PgPool {
+        &self.pool
+    }
+
+    async fn write_jobs(
+        &self,
+        jobs: &[JobAd],
+        on_conflict: OnConflict,
+    ) -> Result<WrittenAds, sqlx::Error> {
+        let started = Instant::now();
+        let jobs = dedup_by_id(jobs, on_conflict);
+        let seen_at = seen_at(&jobs);
+        let mut transaction = self.pool.begin().await?;
+
+        let mut written = WrittenAds::default();
+        let mut statements = 0;
+        for chunk in jobs.chunks(rows_per_statement(MAX_VARIABLES)) {
+            let stored = count_stored_query::<Postgres>(chunk)
+                .build_query_scalar::<i64>()
+                .fetch_one(&mut *transaction)
+                .await?;
+
+            let affected = insert_jobs_query::<Postgres>(chunk, on_conflict)
+                .build()
+                .execute(&mut *transaction)
+                .await?
+                .rows_affected();
+            // Ids are unique within the chunk, so every ad not stored before was inserted
+            let inserted = (chunk.len() as u64).saturating_sub(stored as u64);
+            written.inserted += inserted;
+            written.updated += affected.saturating_sub(inserted);
+            statements += 1;
+
+            mark_seen_query::<Postgres>(&seen_at, chunk)
+                .build()
+                .execute(&mut *transaction)
+                .await?;
+
+            insert_skills(&mut transaction, &extract_skills(chunk)).await?;
+        }
+        transaction.commit().await?;
+
+        info!(
+            "Wrote {} ads in {} statements in {} ms, {} inserted and {} updated",
+            jobs.len(),
+            statements,
+            started.elapsed().as_millis(),
+            written.inserted,
+            written.updated
+        );
+        Ok(written)
+    }
+}
+
+// Skills of stored ads, pairs already stored are skipped
+async fn insert_skills(
+    connection: &mut PgConnection,
+    skills: &[JobSkill],
+) -> Result<u64, sqlx::Error> {
+    let mut inserted = 0;
+    for chunk in skills.chunks(MAX_VARIABLES / 2) {
+        inserted += insert_skills_query::<Postgres>(chunk)
+            .build()
+            .execute(&mut *connection)
+            .await?
+            .rows_affected();
+    }
+    Ok(inserted)
+}
+
+fn user_from_row(row: &PgRow) -> Result<User, sqlx::Error> {
+    let columns = SelectionColumns {
+        occupations: row.try_get("jobcategories")?,
+        regions: row.try_get("regions")?,
+        municipalities: row.try_get("municipalities")?,
+        keyword_watches: row.try_get("keyword_watches")?,
+    };
+    Ok(User {
+        id: row.try_get("id")?,
+        selections: columns.into_selections(),
+    })
+}
+
+#[async_trait]
+impl JobStore for PostgresStore {
+    async fn insert_jobs(&self, jobs: &[JobAd]) -> Result<u64, sqlx::Error> {
+        Ok(self.write_jobs(jobs, OnConflict::Skip).await?.inserted)
+    }
+
+    async fn upsert_jobs(&self, jobs: &[JobAd]) -> Result<WrittenAds, sqlx::Error> {
+        self.write_jobs(jobs, OnConflict::Update).await
+    }
+
+    async fn query_jobs(&self, query: SearchQuery) -> Result<Vec<DbJobAd>, sqlx::Error> {
+        let mut query_builder = QueryBuilder::<Postgres>::new("SELECT * FROM jobs");
+        push_search_filter(&mut query_builder, " WHERE ", query, TextMatch::Ilike);
+        let jobs = query_builder
+            .build_query_as::<DbJobAd>()
+            .fetch_all(&self.pool)
+            .await;
+        match &jobs {
+            Ok(jobs_list) => info!("Fetched {} amount of jobs", jobs_list.len()),
+            Err(e) => error!("Error fetching jobs {:?}", e),
+        }
+        jobs
+    }
+
+    async fn jobs_created_after(&self, created_after: &str) -> Result<Vec<DbJobAd>, sqlx::Error> {
+        sqlx::query_as::<_, DbJobAd>(JOBS_CREATED_AFTER)
+            .bind(created_after)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    async fn count_jobs(&self) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar(COUNT_JOBS).fetch_one(&self.pool).await
+    }
+
+    async fn get_job(&self, id: &str) -> Result<Option<DbJobAd>, sqlx::Error> {
+        sqlx::query_as::<_, DbJobAd>(GET_JOB)
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    async fn list_jobs(&self, listing: &JobListing) -> Result<JobPage, sqlx::Error> {
+        let (mut count, mut query) = listing_queries::<Postgres>(listing, TextMatch::Ilike);
+        let total = count
+            .build_query_scalar::<i64>()
+            .fetch_one(&self.pool)
+            .await?;
+        let jobs = query
+            .build_query_as::<DbJobAd>()
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(JobPage { total, jobs })
+    }
+
+    async fn count_jobs_by(
+        &self,
+        field: JobField,
+        limit: i64,
+    ) -> Result<Vec<GroupCount>, sqlx::Error> {
+        sqlx::query_as::<_, GroupCount>(&count_jobs_by_query(field))
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    async fn daily_counts(&self, created_after: &str) -> Result<Vec<DailyCount>, sqlx::Error> {
+        sqlx::query_as::<_, DailyCount>(DAILY_COUNTS)
+            .bind(created_after)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    async fn employer_ads(
+        &self,
+        created_after: &str,
+        open_on: &str,
+    ) -> Result<Vec<EmployerAd>, sqlx::Error> {
+        sqlx::query_as::<_, EmployerAd>(EMPLOYER_ADS)
+            .bind(created_after)
+            .bind(open_on)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    async fn job_ids_without_details(&self, limit: i64) -> Result<Vec<String>, sqlx::Error> {
+        sqlx::query_scalar(JOB_IDS_WITHOUT_DETAILS)
+            .bind(MAX_DETAILS_ATTEMPTS)
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    async fn mark_details_fetched(&self, id: &str, fetched_at: &str) -> Result<(), sqlx::Error> {
+        sqlx::query(MARK_DETAILS_FETCHED)
+            .bind(fetched_at)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn record_details_failure(&self, id: &str) -> Result<(), sqlx::Error> {
+        sqlx::query(RECORD_DETAILS_FAILURE)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn update_description(&self, id: &str, description: &str) -> Result<(), sqlx::Error> {
+        sqlx::query(UPDATE_DESCRIPTION)
+            .bind(description)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn update_language(
+        &self,
+        id: &str,
+        language: Option<Language>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(UPDATE_LANGUAGE)
+            .bind(language.map(|language| language.to_string()))
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn update_work_mode(
+        &self,
+        id: &str,
+        work_mode: Option<WorkMode>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(UPDATE_WORK_MODE)
+            .bind(work_mode.map(|work_mode| work_mode.to_string()))
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn update_salary(&self, id: &str, salary: &Salary) -> Result<(), sqlx::Error> {
+        sqlx::query(UPDATE_SALARY)
+            .bind(salary.salary_type.map(|kind| kind.to_string()))
+            .bind(salary.description.as_deref().unwrap_or_default())
+            .bind(salary.min)
+            .bind(salary.max)
+            .bind(salary.period.map(|period| period.to_string()))
+            .bind(salary.monthly())
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn salaries(&self, created_after: &str) -> Result<Vec<SalaryRow>, sqlx::Error> {
+        sqlx::query_as::<_, SalaryRow>(SALARIES)
+            .bind(created_after)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    async fn add_job_skills(&self, skills: &[JobSkill]) -> Result<u64, sqlx::Error> {
+        let mut connection = self.pool.acquire().await?;
+        insert_skills(&mut connection, skills).await
+    }
+
+    async fn replace_job_skills(&self, skills: &[JobSkill]) -> Result<(), sqlx::Error> {
+        let mut transaction = self.pool.begin().await?;
+        sqlx::query("DELETE FROM job_skills")
+            .execute(&mut *transaction)
+            .await?;
+        insert_skills(&mut transaction, skills).await?;
+        transaction.commit().await
+    }
+
+    async fn skill_counts(&self, created_after: &str) -> Result<Vec<SkillCount>, sqlx::Error> {
+        sqlx::query_as::<_, SkillCount>(SKILL_COUNTS)
+            .bind(created_after)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    async fn store_user(&self, user: User) -> Result<(), sqlx::Error> {
+        let columns = SelectionColumns::from_selections(user.selections);
+        info!("Inserting or updating user {} in database!", user.id);
+        sqlx::query(STORE_USER)
+            .bind(&user.id)
+            .bind(columns.occupations)
+            .bind(columns.regions)
+            .bind(columns.municipalities)
+            .bind(columns.keyword_watches)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn get_user(&self, user_id: &str) -> Result<Option<User>, sqlx::Error> {
+        let row = sqlx::query(GET_USER)
+            .bind(user_id)
+            .fetch_optional(&self.pool)
+            .await?;
+        row.as_ref().map(user_from_row).transpose()
+    }
+
+    async fn store_subscription(
+        &self,
+        user_id: &str,
+        frequency: DeliveryFrequency,
+        delivery_hour: Option<u32>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(STORE_SUBSCRIPTION)
+            .bind(user_id)
+            .bind(frequency.to_string())
+            .bind(delivery_hour.map(|hour| hour as i32))
+            .execute(&self.pool)
+            .await?;
+
+        info!("Stored {} subscription for user {}", frequency, user_id);
+        Ok(())
+    }
+
+    async fn get_subscribers(&self) -> Result<Vec<Subscriber>, sqlx::Error> {
+        let rows = sqlx::query(SUBSCRIBERS).fetch_all(&self.pool).await?;
+
+        let mut subscribers = Vec::with_capacity(rows.len());
+        for row in rows {
+            let frequency: String = row.try_get("frequency")?;
+            let Ok(frequency) = frequency.parse::<DeliveryFrequency>() else {
+                error!("Unknown delivery frequency '{}' in users table", frequency);
+                continue;
+            };
+            let delivery_hour: Option<i32> = row.try_get("delivery_hour")?;
+            let user = user_from_row(&row)?;
+
+            subscribers.push(Subscriber {
+                user_id: user.id,
+                selections: user.selections,
+                frequency,
+                delivery_hour: delivery_hour.map(|hour| hour as u32),
+                last_delivered_at: row.try_get("last_delivered_at")?,
+            });
+        }
+        Ok(subscribers)
+    }
+
+    async fn mark_delivered(&self, user_id: &str, delivered_at: &str) -> Result<(), sqlx::Error> {
+        sqlx::query(MARK_DELIVERED)
+            .bind(delivered_at)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn start_scrape_run(&self, run: &ScrapeRun) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar(SCRAPE_RUN_START)
+            .bind(&run.started_at)
+            .bind(&run.source)
+            .bind(&run.filters)
+            .fetch_one(&self.pool)
+            .await
+    }
+
+    async fn finish_scrape_run(&self, run: &ScrapeRun) -> Result<(), sqlx::Error> {
+        sqlx::query(SCRAPE_RUN_FINISH)
+            .bind(&run.finished_at)
+            .bind(run.pages_fetched)
+            .bind(run.ads_received)
+            .bind(run.ads_available)
+            .bind(run.ads_inserted)
+            .bind(run.ads_updated)
+            .bind(&run.error)
+            .bind(run.id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn scrape_runs(&self, limit: i64) -> Result<Vec<ScrapeRun>, sqlx::Error> {
+        sqlx::query_as::<_, ScrapeRun>(SCRAPE_RUNS)
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    async fn last_successful_runs(&self) -> Result<Vec<ScrapeRun>, sqlx::Error> {
+        sqlx::query_as::<_, ScrapeRun>(LAST_SUCCESSFUL_RUNS)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    async fn ping(&self) -> Result<(), sqlx::Error> {
+        sqlx::query("SELECT 1").execute(&self.pool).await?;
+        Ok(())
+    }
+}
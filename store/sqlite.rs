@@ -0,0 +1,898 @@
+use super::{
+    count_jobs_by_query, count_stored_query, dedup_by_id, extract_skills, insert_jobs_query,
+    insert_skills_query, listing_queries, mark_seen_query, push_search_filter, rows_per_statement,
+    seen_at, JobStore, OnConflict, SelectionColumns, TextMatch, COUNT_JOBS, DAILY_COUNTS,
+    EMPLOYER_ADS, GET_JOB, GET_USER, JOBS_CREATED_AFTER, JOB_IDS_WITHOUT_DETAILS,
+    LAST_SUCCESSFUL_RUNS, MARK_DELIVERED, MARK_DETAILS_FETCHED, RECORD_DETAILS_FAILURE, SALARIES,
+    SCRAPE_RUNS, SCRAPE_RUN_FINISH, SCRAPE_RUN_START, SKILL_COUNTS, STORE_SUBSCRIPTION, STORE_USER,
+    SUBSCRIBERS, UPDATE_DESCRIPTION, UPDATE_LANGUAGE, UPDATE_SALARY, UPDATE_WORK_MODE,
+};
+use crate::constants::MAX_DETAILS_ATTEMPTS;
+use crate::database::{
+    DailyCount, DbJobAd, EmployerAd, GroupCount, JobField, JobListing, JobPage, JobSkill,
+    SalaryRow, ScrapeRun, SearchQuery, SkillCount, Subscriber, User, WrittenAds,
+};
+use crate::find_jobs::JobAd;
+use crate::language::Language;
+use crate::logging::{error, info};
+use crate::salary::Salary;
+use crate::subscription::DeliveryFrequency;
+use crate::work_mode::WorkMode;
+use async_trait::async_trait;
+use sqlx::sqlite::{SqliteConnection, SqlitePoolOptions, SqliteRow};
+use sqlx::{QueryBuilder, Row, Sqlite, SqlitePool};
+use std::time::Instant;
+
+// SQLITE_MAX_VARIABLE_NUMBER of SQLite 3.32 and later
+const MAX_VARIABLES: usize = 32766;
+
+pub struct SqliteStore {
+    pool: SqlitePool,
+}
+
+impl SqliteStore {
+    pub async fn connect(database_url: &str) -> Result<Self, sqlx::Error> {
+        let pool = SqlitePool::connect(database_url).await?;
+        sqlx::migrate!("./migrations/sqlite").run(&pool).await?;
+        Ok(Self { pool })
+    }
+
+    /// A private in-memory database with migrations applied, every call gets a new database.
+    pub async fn in_memory() -> Result<Self, sqlx::Error> {
+        // The database lives as long as its connection, so keep exactly one open
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .min_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connect("sqlite::memory:")
+            .await?;
+        sqlx::migrate!("./migrations/sqlite").run(&pool).await?;
+        Ok(Self { pool })
+    }
+
+    pub fn pool(&self) -> &SqlitePool {
+        &self.pool
+    }
+
+    async fn write_jobs(
+        &self,
+        jobs: &[JobAd],
+        on_conflict: OnConflict,
+    ) -> Result<WrittenAds, sqlx::Error> {
+        let started = Instant::now();
+        let jobs = dedup_by_id(jobs, on_conflict);
+        let seen_at = seen_at(&jobs);
+        let mut transaction = self.pool.begin().await?;
+
+        let mut written = WrittenAds::default();
+        let mut statements = 0;
+        for chunk in jobs.chunks(rows_per_statement(MAX_VARIABLES)) {
+            let stored = count_stored_query::<Sqlite>(chunk)
+                .build_query_scalar::<i64>()
+                .fetch_one(&mut *transaction)
+                .await?;
+
+            let affected = insert_jobs_query::<Sqlite>(chunk, on_conflict)
+                .build()
+                .execute(&mut *transaction)
+                .await?
+                .rows_affected();
+            // Ids are unique within the chunk, so every ad not stored before was inserted
+            let inserted = (chunk.len() as u64).saturating_sub(stored as u64);
+            written.inserted += inserted;
+            written.updated += affected.saturating_sub(inserted);
+            statements += 1;
+
+            mark_seen_query::<Sqlite>(&seen_at, chunk)
+                .build()
+                .execute(&mut *transaction)
+                .await?;
+
+            insert_skills(&mut transaction, &extract_skills(chunk)).await?;
+        }
+        transaction.commit().await?;
+
+        info!(
+            "Wrote {} ads in {} statements in {} ms, {} inserted and {} updated",
+            jobs.len(),
+            statements,
+            started.elapsed().as_millis(),
+            written.inserted,
+            written.updated
+        );
+        Ok(written)
+    }
+}
+
+// Skills of stored ads, pairs already stored are skipped
+async fn insert_skills(
+    connection: &mut SqliteConnection,
+    skills: &[JobSkill],
+) -> Result<u64, sqlx::Error> {
+    let mut inserted = 0;
+    for chunk in skills.chunks(MAX_VARIABLES / 2) {
+        inserted += insert_skills_query::<Sqlite>(chunk)
+            .build()
+            .execute(&mut *connection)
+            .await?
+            .rows_affected();
+    }
+    Ok(inserted)
+}
+
+fn user_from_row(row: &SqliteRow) -> Result<User, sqlx::Error> {
+    let columns = SelectionColumns {
+        occupations: row.try_get("jobcategories")?,
+        regions: row.try_get("regions")?,
+        municipalities: row.try_get("municipalities")?,
+        keyword_watches: row.try_get("keyword_watches")?,
+    };
+    Ok(User {
+        id: row.try_get("id")?,
+        selections: columns.into_selections(),
+    })
+}
+
+#[async_trait]
+impl JobStore for SqliteStore {
+    async fn insert_jobs(&self, jobs: &[JobAd]) -> Result<u64, sqlx::Error> {
+        Ok(self.write_jobs(jobs, OnConflict::Skip).await?.inserted)
+    }
+
+    async fn upsert_jobs(&self, jobs: &[JobAd]) -> Result<WrittenAds, sqlx::Error> {
+        self.write_jobs(jobs, OnConflict::Update).await
+    }
+
+    async fn query_jobs(&self, query: SearchQuery) -> Result<Vec<DbJobAd>, sqlx::Error> {
+        let mut query_builder = QueryBuilder::<Sqlite>::new("SELECT * FROM jobs");
+        push_search_filter(&mut query_builder, " WHERE ", query, TextMatch::Glob);
+        let jobs = query_builder
+            .build_query_as::<DbJobAd>()
+            .fetch_all(&self.pool)
+            .await;
+        match &jobs {
+            Ok(jobs_list) => info!("Fetched {} amount of jobs", jobs_list.len()),
+            Err(e) => error!("Error fetching jobs {:?}", e),
+        }
+        jobs
+    }
+
+    async fn jobs_created_after(&self, created_after: &str) -> Result<Vec<DbJobAd>, sqlx::Error> {
+        sqlx::query_as::<_, DbJobAd>(JOBS_CREATED_AFTER)
+            .bind(created_after)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    async fn count_jobs(&self) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar(COUNT_JOBS).fetch_one(&self.pool).await
+    }
+
+    async fn get_job(&self, id: &str) -> Result<Option<DbJobAd>, sqlx::Error> {
+        sqlx::query_as::<_, DbJobAd>(GET_JOB)
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    async fn list_jobs(&self, listing: &JobListing) -> Result<JobPage, sqlx::Error> {
+        let (mut count, mut query) = listing_queries::<Sqlite>(listing, TextMatch::Glob);
+        let total = count
+            .build_query_scalar::<i64>()
+            .fetch_one(&self.pool)
+            .await?;
+        let jobs = query
+            .build_query_as::<DbJobAd>()
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(JobPage { total, jobs })
+    }
+
+    async fn count_jobs_by(
+        &self,
+        field: JobField,
+        limit: i64,
+    ) -> Result<Vec<GroupCount>, sqlx::Error> {
+        sqlx::query_as::<_, GroupCount>(&count_jobs_by_query(field))
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    async fn daily_counts(&self, created_after: &str) -> Result<Vec<DailyCount>, sqlx::Error> {
+        sqlx::query_as::<_, DailyCount>(DAILY_COUNTS)
+            .bind(created_after)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    async fn employer_ads(
+        &self,
+        created_after: &str,
+        open_on: &str,
+    ) -> Result<Vec<EmployerAd>, sqlx::Error> {
+        sqlx::query_as::<_, EmployerAd>(EMPLOYER_ADS)
+            .bind(created_after)
+            .bind(open_on)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    async fn job_ids_without_details(&self, limit: i64) -> Result<Vec<String>, sqlx::Error> {
+        sqlx::query_scalar(JOB_IDS_WITHOUT_DETAILS)
+            .bind(MAX_DETAILS_ATTEMPTS)
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    async fn mark_details_fetched(&self, id: &str, fetched_at: &str) -> Result<(), sqlx::Error> {
+        sqlx::query(MARK_DETAILS_FETCHED)
+            .bind(fetched_at)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn record_details_failure(&self, id: &str) -> Result<(), sqlx::Error> {
+        sqlx::query(RECORD_DETAILS_FAILURE)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn update_description(&self, id: &str, description: &str) -> Result<(), sqlx::Error> {
+        sqlx::query(UPDATE_DESCRIPTION)
+            .bind(description)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn update_language(
+        &self,
+        id: &str,
+        language: Option<Language>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(UPDATE_LANGUAGE)
+            .bind(language.map(|language| language.to_string()))
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn update_work_mode(
+        &self,
+        id: &str,
+        work_mode: Option<WorkMode>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(UPDATE_WORK_MODE)
+            .bind(work_mode.map(|work_mode| work_mode.to_string()))
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn update_salary(&self, id: &str, salary: &Salary) -> Result<(), sqlx::Error> {
+        sqlx::query(UPDATE_SALARY)
+            .bind(salary.salary_type.map(|kind| kind.to_string()))
+            .bind(salary.description.as_deref().unwrap_or_default())
+            .bind(salary.min)
+            .bind(salary.max)
+            .bind(salary.period.map(|period| period.to_string()))
+            .bind(salary.monthly())
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn salaries(&self, created_after: &str) -> Result<Vec<SalaryRow>, sqlx::Error> {
+        sqlx::query_as::<_, SalaryRow>(SALARIES)
+            .bind(created_after)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    async fn add_job_skills(&self, skills: &[JobSkill]) -> Result<u64, sqlx::Error> {
+        let mut connection = self.pool.acquire().await?;
+        insert_skills(&mut connection, skills).await
+    }
+
+    async fn replace_job_skills(&self, skills: &[JobSkill]) -> Result<(), sqlx::Error> {
+        let mut transaction = self.pool.begin().await?;
+        sqlx::query("DELETE FROM job_skills")
+            .execute(&mut *transaction)
+            .await?;
+        insert_skills(&mut transaction, skills).await?;
+        transaction.commit().await
+    }
+
+    async fn skill_counts(&self, created_after: &str) -> Result<Vec<SkillCount>, sqlx::Error> {
+        sqlx::query_as::<_, SkillCount>(SKILL_COUNTS)
+            .bind(created_after)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    async fn store_user(&self, user: User) -> Result<(), sqlx::Error> {
+        let columns = SelectionColumns::from_selections(user.selections);
+        info!("Inserting or updating user {} in database!", user.id);
+        sqlx::query(STORE_USER)
+            .bind(&user.id)
+            .bind(columns.occupations)
+            .bind(columns.regions)
+            .bind(columns.municipalities)
+            .bind(columns.keyword_watches)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn get_user(&self, user_id: &str) -> Result<Option<User>, sqlx::Error> {
+        let row = sqlx::query(GET_USER)
+            .bind(user_id)
+            .fetch_optional(&self.pool)
+            .await?;
+        row.as_ref().map(user_from_row).transpose()
+    }
+
+    async fn store_subscription(
+        &self,
+        user_id: &str,
+        frequency: DeliveryFrequency,
+        delivery_hour: Option<u32>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(STORE_SUBSCRIPTION)
+            .bind(user_id)
+            .bind(frequency.to_string())
+            .bind(delivery_hour.map(|hour| hour as i32))
+            .execute(&self.pool)
+            .await?;
+
+        info!("Stored {} subscription for user {}", frequency, user_id);
+        Ok(())
+    }
+
+    async fn get_subscribers(&self) -> Result<Vec<Subscriber>, sqlx::Error> {
+        let rows = sqlx::query(SUBSCRIBERS).fetch_all(&self.pool).await?;
+
+        let mut subscribers = Vec::with_capacity(rows.len());
+        for row in rows {
+            let frequency: String = row.try_get("frequency")?;
+            let Ok(frequency) = frequency.parse::<DeliveryFrequency>() else {
+                error!("Unknown delivery frequency '{}' in users table", frequency);
+                continue;
+            };
+            let delivery_hour: Option<i32> = row.try_get("delivery_hour")?;
+            let user = user_from_row(&row)?;
+
+            subscribers.push(Subscriber {
+                user_id: user.id,
+                selections: user.selections,
+                frequency,
+                delivery_hour: delivery_hour.map(|hour| hour as u32),
+                last_delivered_at: row.try_get("last_delivered_at")?,
+            });
+        }
+        Ok(subscribers)
+    }
+
+    async fn mark_delivered(&self, user_id: &str, delivered_at: &str) -> Result<(), sqlx::Error> {
+        sqlx::query(MARK_DELIVERED)
+            .bind(delivered_at)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn start_scrape_run(&self, run: &ScrapeRun) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar(SCRAPE_RUN_START)
+            .bind(&run.started_at)
+            .bind(&run.source)
+            .bind(&run.filters)
+            .fetch_one(&self.pool)
+            .await
+    }
+
+    async fn finish_scrape_run(&self, run: &ScrapeRun) -> Result<(), sqlx::Error> {
+        sqlx::query(SCRAPE_RUN_FINISH)
+            .bind(&run.finished_at)
+            .bind(run.pages_fetched)
+            .bind(run.ads_received)
+            .bind(run.ads_available)
+            .bind(run.ads_inserted)
+            .bind(run.ads_updated)
+            .bind(&run.error)
+            .bind(run.id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn scrape_runs(&self, limit: i64) -> Result<Vec<ScrapeRun>, sqlx::Error> {
+        sqlx::query_as::<_, ScrapeRun>(SCRAPE_RUNS)
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    async fn last_successful_runs(&self) -> Result<Vec<ScrapeRun>, sqlx::Error> {
+        sqlx::query_as::<_, ScrapeRun>(LAST_SUCCESSFUL_RUNS)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    async fn ping(&self) -> Result<(), sqlx::Error> {
+        sqlx::query("SELECT 1").execute(&self.pool).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::JobSort;
+    use crate::find_jobs::Region;
+    use crate::test_fixtures::{job, user};
+    use chrono::{Duration, Utc};
+
+    #[tokio::test]
+    async fn insert_ignores_already_stored_ads() {
+        let store = SqliteStore::in_memory().await.unwrap();
+
+        let first = store
+            .insert_jobs(&[job("1").build(), job("2").build()])
+            .await
+            .unwrap();
+        let second = store
+            .insert_jobs(&[job("2").title("Ändrad titel").build(), job("3").build()])
+            .await
+            .unwrap();
+
+        assert_eq!(first, 2);
+        assert_eq!(second, 1);
+        assert_eq!(store.count_jobs().await.unwrap(), 3);
+        let titles = store
+            .query_jobs(SearchQuery::Title(vec!["Ändrad".into()]))
+            .await
+            .unwrap();
+        assert!(titles.is_empty());
+    }
+
+    #[tokio::test]
+    async fn upsert_updates_ads_and_keeps_fetched_description() {
+        let store = SqliteStore::in_memory().await.unwrap();
+        store
+            .insert_jobs(&[job("1").description("Vi söker en utvecklare").build()])
+            .await
+            .unwrap();
+
+        let written = store
+            .upsert_jobs(&[
+                job("1").title("Senior utvecklare").build(),
+                job("2").build(),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(
+            written,
+            WrittenAds {
+                inserted: 1,
+                updated: 1
+            }
+        );
+
+        let job = store.get_job("1").await.unwrap().unwrap();
+        assert_eq!(job.title.as_deref(), Some("Senior utvecklare"));
+        assert_eq!(job.description.as_deref(), Some("Vi söker en utvecklare"));
+    }
+
+    #[tokio::test]
+    async fn insert_moves_last_seen_of_already_stored_ads() {
+        let store = SqliteStore::in_memory().await.unwrap();
+        let first = job("1").created_at("2026-10-01T08:00:00.000Z");
+        store.insert_jobs(&[first.build()]).await.unwrap();
+        let again = job("1").created_at("2026-10-04T08:00:00.000Z");
+        assert_eq!(store.insert_jobs(&[again.build()]).await.unwrap(), 0);
+
+        let ads = store
+            .employer_ads("2026-09-01T00:00:00.000Z", "2026-10-18")
+            .await
+            .unwrap();
+        assert_eq!(ads.len(), 1);
+        assert_eq!(
+            ads[0].created_at.as_deref(),
+            Some("2026-10-01T08:00:00.000Z")
+        );
+        assert_eq!(
+            ads[0].last_seen_at.as_deref(),
+            Some("2026-10-04T08:00:00.000Z")
+        );
+    }
+
+    #[tokio::test]
+    async fn query_matches_any_value_case_insensitively() {
+        let store = SqliteStore::in_memory().await.unwrap();
+        store
+            .insert_jobs(&[
+                job("1").employer("Spotify AB").build(),
+                job("2").employer("Klarna Bank AB").build(),
+                job("3").employer("Volvo Cars").build(),
+            ])
+            .await
+            .unwrap();
+
+        let mut jobs = store
+            .query_jobs(SearchQuery::Company(vec![
+                "spotify".into(),
+                "klarna".into(),
+            ]))
+            .await
+            .unwrap();
+        jobs.sort_by(|a, b| a.id.cmp(&b.id));
+
+        let ids = jobs
+            .iter()
+            .filter_map(|job| job.id.as_deref())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec!["1", "2"]);
+    }
+
+    #[tokio::test]
+    async fn municipality_query_matches_the_whole_workplace() {
+        let store = SqliteStore::in_memory().await.unwrap();
+        store
+            .insert_jobs(&[
+                job("1").workplace("Lund").build(),
+                job("2").workplace("Lunds universitet, Uppsala").build(),
+                job("3").workplace("Malmö").build(),
+            ])
+            .await
+            .unwrap();
+
+        // Lund by concept id, Malmö by name
+        let mut jobs = store
+            .query_jobs(SearchQuery::Municipality(vec![
+                "muSY_tsR_vDZ".into(),
+                "malmö".into(),
+            ]))
+            .await
+            .unwrap();
+        jobs.sort_by(|a, b| a.id.cmp(&b.id));
+
+        let ids = jobs
+            .iter()
+            .filter_map(|job| job.id.as_deref())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec!["1", "3"]);
+    }
+
+    #[tokio::test]
+    async fn text_filters_fold_non_ascii_case_and_take_wildcards_literally() {
+        let store = SqliteStore::in_memory().await.unwrap();
+        store
+            .insert_jobs(&[
+                job("1")
+                    .workplace("Örebro")
+                    .title("Utvecklare 100% distans")
+                    .build(),
+                job("2")
+                    .workplace("Öckerö")
+                    .title("Utvecklare 100 procent")
+                    .build(),
+            ])
+            .await
+            .unwrap();
+
+        let jobs = store
+            .query_jobs(SearchQuery::Location(vec!["örebro".into()]))
+            .await
+            .unwrap();
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0].id.as_deref(), Some("1"));
+
+        let jobs = store
+            .query_jobs(SearchQuery::Title(vec!["100%".into()]))
+            .await
+            .unwrap();
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0].id.as_deref(), Some("1"));
+        let jobs = store
+            .query_jobs(SearchQuery::Title(vec!["*".into()]))
+            .await
+            .unwrap();
+        assert!(jobs.is_empty());
+    }
+
+    #[tokio::test]
+    async fn jobs_created_after_excludes_older_ads() {
+        let store = SqliteStore::in_memory().await.unwrap();
+        store
+            .insert_jobs(&[
+                job("old").created_at("2026-10-17T08:00:00.000Z").build(),
+                job("new").created_at("2026-10-18T09:00:00.000Z").build(),
+            ])
+            .await
+            .unwrap();
+
+        let jobs = store
+            .jobs_created_after("2026-10-18T00:00:00.000Z")
+            .await
+            .unwrap();
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0].id.as_deref(), Some("new"));
+    }
+
+    #[tokio::test]
+    async fn most_recent_query_compares_creation_times() {
+        let store = SqliteStore::in_memory().await.unwrap();
+        let created_at = |age: Duration| {
+            (Utc::now() - age)
+                .format("%Y-%m-%dT%H:%M:%S.%3fZ")
+                .to_string()
+        };
+        store
+            .insert_jobs(&[
+                job("old")
+                    .created_at(&created_at(Duration::days(3)))
+                    .build(),
+                job("new")
+                    .created_at(&created_at(Duration::hours(1)))
+                    .build(),
+            ])
+            .await
+            .unwrap();
+
+        let jobs = store
+            .query_jobs(SearchQuery::MostRecent(Duration::days(1)))
+            .await
+            .unwrap();
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0].id.as_deref(), Some("new"));
+    }
+
+    #[tokio::test]
+    async fn stores_and_loads_user_selections() {
+        let store = SqliteStore::in_memory().await.unwrap();
+        store
+            .store_user(
+                user("42")
+                    .region(Region::Skåne)
+                    .keywords("rust -konsult")
+                    .build(),
+            )
+            .await
+            .unwrap();
+
+        let stored = store.get_user("42").await.unwrap().unwrap();
+        assert!(stored
+            .selections
+            .selected_regions
+            .unwrap()
+            .contains(&Region::Skåne));
+        let watches = stored.selections.keyword_watches.unwrap();
+        assert_eq!(watches[0].include, vec!["rust"]);
+        assert_eq!(watches[0].exclude, vec!["konsult"]);
+        assert!(store.get_user("43").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn storing_new_user_keeps_existing_selections() {
+        let store = SqliteStore::in_memory().await.unwrap();
+        store
+            .store_user(user("42").region(Region::Skåne).build())
+            .await
+            .unwrap();
+
+        // /start stores a user without any selections
+        store.store_user(User::new("42".into())).await.unwrap();
+
+        let stored = store.get_user("42").await.unwrap().unwrap();
+        assert!(stored
+            .selections
+            .selected_regions
+            .unwrap()
+            .contains(&Region::Skåne));
+    }
+
+    #[tokio::test]
+    async fn subscribers_include_delivery_settings() {
+        let store = SqliteStore::in_memory().await.unwrap();
+        store.store_user(user("1").build()).await.unwrap();
+        store.store_user(user("2").build()).await.unwrap();
+        store
+            .store_subscription("2", DeliveryFrequency::Daily, Some(8))
+            .await
+            .unwrap();
+        store
+            .mark_delivered("2", "2026-10-18T06:00:00.000Z")
+            .await
+            .unwrap();
+
+        let subscribers = store.get_subscribers().await.unwrap();
+        assert_eq!(subscribers.len(), 1);
+        assert_eq!(subscribers[0].user_id, "2");
+        assert_eq!(subscribers[0].frequency, DeliveryFrequency::Daily);
+        assert_eq!(subscribers[0].delivery_hour, Some(8));
+        assert_eq!(
+            subscribers[0].last_delivered_at.as_deref(),
+            Some("2026-10-18T06:00:00.000Z")
+        );
+    }
+
+    #[tokio::test]
+    async fn insert_splits_large_batches_into_several_statements() {
+        let store = SqliteStore::in_memory().await.unwrap();
+        let total = rows_per_statement(MAX_VARIABLES) * 2 + 5;
+        let jobs = (0..total)
+            .map(|i| job(&format!("bulk-{}", i)).build())
+            .collect::<Vec<_>>();
+
+        assert_eq!(store.insert_jobs(&jobs).await.unwrap(), total as u64);
+        assert_eq!(store.count_jobs().await.unwrap(), total as i64);
+    }
+
+    #[tokio::test]
+    async fn upsert_keeps_the_last_copy_of_duplicates_in_a_batch() {
+        let store = SqliteStore::in_memory().await.unwrap();
+
+        store
+            .upsert_jobs(&[
+                job("1").title("Första versionen").build(),
+                job("2").build(),
+                job("1").title("Andra versionen").build(),
+            ])
+            .await
+            .unwrap();
+
+        assert_eq!(store.count_jobs().await.unwrap(), 2);
+        let jobs = store
+            .query_jobs(SearchQuery::Title(vec!["versionen".into()]))
+            .await
+            .unwrap();
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0].title.as_deref(), Some("Andra versionen"));
+    }
+
+    #[tokio::test]
+    async fn insert_keeps_the_first_copy_and_skips_ads_without_id() {
+        let store = SqliteStore::in_memory().await.unwrap();
+
+        let inserted = store
+            .insert_jobs(&[
+                job("1").title("Första versionen").build(),
+                JobAd {
+                    id: None,
+                    ..job("2").title("Utan id").build()
+                },
+                job("1").title("Andra versionen").build(),
+                JobAd {
+                    id: None,
+                    ..job("3").title("Också utan id").build()
+                },
+            ])
+            .await
+            .unwrap();
+
+        assert_eq!(inserted, 1);
+        assert_eq!(store.count_jobs().await.unwrap(), 1);
+        let job = store.get_job("1").await.unwrap().unwrap();
+        assert_eq!(job.title.as_deref(), Some("Första versionen"));
+    }
+
+    #[tokio::test]
+    async fn scrape_runs_are_listed_newest_first() {
+        let store = SqliteStore::in_memory().await.unwrap();
+        for (started_at, source) in [
+            ("2026-10-17T06:00:00.000Z", "pb"),
+            ("2026-10-18T06:00:00.000Z", "external"),
+        ] {
+            let mut run = ScrapeRun {
+                started_at: started_at.to_string(),
+                source: source.to_string(),
+                ..Default::default()
+            };
+            run.id = store.start_scrape_run(&run).await.unwrap();
+            if source == "pb" {
+                run.finished_at = Some("2026-10-17T06:05:00.000Z".to_string());
+                run.pages_fetched = 3;
+                run.ads_received = 210;
+                run.ads_available = 215;
+                run.ads_inserted = 200;
+                store.finish_scrape_run(&run).await.unwrap();
+            }
+        }
+
+        let runs = store.scrape_runs(10).await.unwrap();
+        assert_eq!(runs.len(), 2);
+        assert_eq!(runs[0].source, "external");
+        assert!(runs[0].finished_at.is_none());
+        assert_eq!(runs[1].source, "pb");
+        assert_eq!(runs[1].pages_fetched, 3);
+        assert_eq!(runs[1].ads_available, 215);
+        assert_eq!(runs[1].ads_inserted, 200);
+        assert!(runs[1].error.is_none());
+    }
+
+    #[tokio::test]
+    async fn list_jobs_filters_sorts_and_pages() {
+        let store = SqliteStore::in_memory().await.unwrap();
+        store
+            .insert_jobs(&[
+                job("1")
+                    .workplace("Malmö")
+                    .created_at("2026-10-16T08:00:00.000Z")
+                    .build(),
+                job("2")
+                    .workplace("Göteborg")
+                    .created_at("2026-10-17T08:00:00.000Z")
+                    .build(),
+                job("3")
+                    .workplace("Malmö")
+                    .created_at("2026-10-18T08:00:00.000Z")
+                    .build(),
+                job("4")
+                    .workplace("Stockholm")
+                    .created_at("2026-10-18T09:00:00.000Z")
+                    .build(),
+            ])
+            .await
+            .unwrap();
+
+        let listing = JobListing {
+            filters: vec![SearchQuery::Location(vec![
+                "malmö".to_string(),
+                "göteborg".to_string(),
+            ])],
+            created_after: Some("2026-10-17T00:00:00.000Z".to_string()),
+            sort: JobSort::Created,
+            descending: true,
+            limit: 1,
+            ..Default::default()
+        };
+        let page = store.list_jobs(&listing).await.unwrap();
+        assert_eq!(page.total, 2);
+        assert_eq!(page.jobs.len(), 1);
+        assert_eq!(page.jobs[0].id.as_deref(), Some("3"));
+
+        let page = store
+            .list_jobs(&JobListing {
+                offset: 1,
+                ..listing
+            })
+            .await
+            .unwrap();
+        assert_eq!(page.jobs[0].id.as_deref(), Some("2"));
+        assert!(store.get_job("4").await.unwrap().is_some());
+        assert!(store.get_job("5").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn count_jobs_by_groups_largest_first() {
+        let store = SqliteStore::in_memory().await.unwrap();
+        store
+            .insert_jobs(&[
+                job("1").employer("Alfa AB").build(),
+                job("2").employer("Beta AB").build(),
+                job("3").employer("Beta AB").build(),
+            ])
+            .await
+            .unwrap();
+
+        let counts = store.count_jobs_by(JobField::Employer, 10).await.unwrap();
+        let counts = counts
+            .iter()
+            .map(|group| (group.value.as_deref(), group.count))
+            .collect::<Vec<_>>();
+        assert_eq!(counts, vec![(Some("Beta AB"), 2), (Some("Alfa AB"), 1)]);
+    }
+}
@@ -0,0 +1,433 @@
+//! Digest delivery for subscribed bot users.
+use crate::bot::UserSelections;
+use crate::context::AppContext;
+use crate::database::{DbJobAd, Subscriber};
+use crate::find_jobs::OccupationType;
+use crate::logging::{error, info};
+use crate::metrics;
+use crate::municipality::find_municipality;
+use chrono::{DateTime, Duration, Timelike, Utc};
+use chrono_tz::Europe::Stockholm;
+use chrono_tz::Tz;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::LazyLock;
+use strum_macros::{Display, EnumIter, EnumString};
+use teloxide::prelude::*;
+use teloxide::types::{InlineKeyboardButton, InlineKeyboardMarkup};
+use tokio::sync::Mutex;
+
+pub const MAX_DIGEST_LENGTH: usize = 3500;
+pub const SCHEDULER_INTERVAL_SECS: u64 = 60;
+pub const CALLBACK_SHOW_MORE: &str = "visa_fler";
+
+static PENDING_PAGES: LazyLock<Mutex<HashMap<ChatId, VecDeque<String>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Display, EnumString, EnumIter)]
+#[strum(serialize_all = "lowercase")]
+pub enum DeliveryFrequency {
+    Instant,
+    Daily,
+    Weekly,
+}
+
+impl DeliveryFrequency {
+    pub fn as_readable_string(&self) -> &'static str {
+        match self {
+            DeliveryFrequency::Instant => "Direkt",
+            DeliveryFrequency::Daily => "Dagligen",
+            DeliveryFrequency::Weekly => "Veckovis",
+        }
+    }
+
+    fn period(&self) -> Duration {
+        match self {
+            DeliveryFrequency::Instant => Duration::minutes(SCHEDULER_INTERVAL_SECS as i64 / 60),
+            DeliveryFrequency::Daily => Duration::days(1),
+            DeliveryFrequency::Weekly => Duration::weeks(1),
+        }
+    }
+}
+
+pub async fn run_scheduler(ctx: AppContext) {
+    let Some(bot) = ctx.bot.clone() else {
+        error!("No bot to deliver digests with");
+        return;
+    };
+    let mut interval =
+        tokio::time::interval(std::time::Duration::from_secs(SCHEDULER_INTERVAL_SECS));
+    loop {
+        interval.tick().await;
+        if let Err(e) = deliver_due_digests(&ctx, &bot).await {
+            error!("Failed to deliver digests: {:?}", e);
+        }
+    }
+}
+
+async fn deliver_due_digests(
+    ctx: &AppContext,
+    bot: &Bot,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let now = Utc::now();
+    let local_now = now.with_timezone(&Stockholm);
+
+    // One failing subscriber, such as a user who blocked the bot, must not stop the others
+    for subscriber in ctx.store.get_subscribers().await? {
+        if !is_due(&subscriber, &local_now) {
+            continue;
+        }
+        if let Err(e) = deliver_digest(ctx, bot, &subscriber, now).await {
+            error!(
+                "Failed to deliver digest to user {}: {:?}",
+                subscriber.user_id, e
+            );
+        }
+    }
+    Ok(())
+}
+
+async fn deliver_digest(
+    ctx: &AppContext,
+    bot: &Bot,
+    subscriber: &Subscriber,
+    now: DateTime<Utc>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let created_after = subscriber.last_delivered_at.clone().unwrap_or_else(|| {
+        (now - subscriber.frequency.period())
+            .format("%Y-%m-%dT%H:%M:%S.%3fZ")
+            .to_string()
+    });
+    let jobs = ctx
+        .store
+        .jobs_created_after(&created_after)
+        .await?
+        .into_iter()
+        .filter(|job| matches_selections(job, &subscriber.selections))
+        .collect::<Vec<_>>();
+
+    let delivered_at = now.format("%Y-%m-%dT%H:%M:%S.%3fZ").to_string();
+    if !jobs.is_empty() {
+        let chat_id = ChatId(subscriber.user_id.parse()?);
+        let pages = build_digest_pages(&jobs, subscriber.frequency);
+        send_pages(bot, chat_id, pages).await?;
+        info!(
+            "Delivered {} ads to user {}",
+            jobs.len(),
+            subscriber.user_id
+        );
+    }
+    ctx.store
+        .mark_delivered(&subscriber.user_id, &delivered_at)
+        .await?;
+    Ok(())
+}
+
+fn is_due(subscriber: &Subscriber, now: &DateTime<Tz>) -> bool {
+    let last_delivered = subscriber
+        .last_delivered_at
+        .as_deref()
+        .and_then(|date| DateTime::parse_from_rfc3339(date).ok())
+        .map(|date| date.with_timezone(&Stockholm));
+    let at_hour = subscriber
+        .delivery_hour
+        .is_none_or(|hour| now.hour() == hour);
+    let days_since_delivery = |days: i64| {
+        last_delivered.is_none_or(|last| (now.date_naive() - last.date_naive()).num_days() >= days)
+    };
+
+    // A weekly digest missed at its hour goes out at the same hour the next day
+    match subscriber.frequency {
+        DeliveryFrequency::Instant => true,
+        DeliveryFrequency::Daily => at_hour && days_since_delivery(1),
+        DeliveryFrequency::Weekly => at_hour && days_since_delivery(7),
+    }
+}
+
+pub fn matches_selections(job: &DbJobAd, selections: &UserSelections) -> bool {
+    let workplace = job.workplace.as_deref().unwrap_or_default();
+    let municipality = find_municipality(workplace);
+    let regions = selections
+        .selected_regions
+        .as_ref()
+        .filter(|r| !r.is_empty());
+    let municipalities = selections
+        .selected_municipalities
+        .as_ref()
+        .filter(|m| !m.is_empty());
+
+    let region_match = regions.map(|regions| match municipality {
+        Some(municipality) => regions.contains(&municipality.region),
+        None => regions.iter().any(|region| {
+            workplace
+                .to_lowercase()
+                .contains(&format!("{:?}", region).to_lowercase())
+        }),
+    });
+    let municipality_match = municipalities.map(|municipalities| {
+        municipality.is_some_and(|municipality| municipalities.contains(&municipality.id))
+    });
+    let location_match = match (region_match, municipality_match) {
+        (None, None) => true,
+        (region_match, municipality_match) => {
+            region_match.unwrap_or(false) || municipality_match.unwrap_or(false)
+        }
+    };
+
+    let occupation_match = match &selections.selected_occupations {
+        Some(occupations) if !occupations.is_empty() => job
+            .occupation
+            .as_deref()
+            .and_then(OccupationType::from_occupation_name)
+            .is_some_and(|occupation| occupations.contains(&occupation)),
+        _ => true,
+    };
+    let keyword_match = match &selections.keyword_watches {
+        Some(watches) if !watches.is_empty() => watches.iter().any(|watch| watch.matches(job)),
+        _ => true,
+    };
+    location_match && occupation_match && keyword_match
+}
+
+pub fn build_digest_pages(jobs: &[DbJobAd], frequency: DeliveryFrequency) -> Vec<String> {
+    let mut by_occupation: BTreeMap<&str, Vec<&DbJobAd>> = BTreeMap::new();
+    for job in jobs {
+        let occupation = job.occupation.as_deref().unwrap_or("Övrigt");
+        by_occupation.entry(occupation).or_default().push(job);
+    }
+
+    let header = match frequency {
+        DeliveryFrequency::Instant => format!("🔔 {} nya annonser\n\n", jobs.len()),
+        DeliveryFrequency::Daily => {
+            format!("📅 Dagens sammanfattning: {} nya annonser\n\n", jobs.len())
+        }
+        DeliveryFrequency::Weekly => {
+            format!("🗓 Veckans sammanfattning: {} nya annonser\n\n", jobs.len())
+        }
+    };
+
+    let mut pages = Vec::new();
+    let mut page = header;
+    for (occupation, jobs) in by_occupation {
+        let heading = format!("💼 {} ({})\n", occupation, jobs.len());
+        push_line(&mut pages, &mut page, &heading);
+        for job in jobs {
+            let line = format!(
+                "• {} – {}, {}\n{}\n",
+                job.title.as_deref().unwrap_or("Okänd titel"),
+                job.workplace_name
+                    .as_deref()
+                    .unwrap_or("Okänd arbetsgivare"),
+                job.workplace.as_deref().unwrap_or("Okänd ort"),
+                job.url.as_deref().unwrap_or_default(),
+            );
+            push_line(&mut pages, &mut page, &line);
+        }
+        push_line(&mut pages, &mut page, "\n");
+    }
+    if !page.trim().is_empty() {
+        pages.push(page);
+    }
+    pages
+}
+
+fn push_line(pages: &mut Vec<String>, page: &mut String, line: &str) {
+    if page.chars().count() + line.chars().count() > MAX_DIGEST_LENGTH && !page.is_empty() {
+        pages.push(std::mem::take(page));
+    }
+    page.push_str(line);
+}
+
+async fn send_pages(bot: &Bot, chat_id: ChatId, pages: Vec<String>) -> ResponseResult<()> {
+    let mut pages = VecDeque::from(pages);
+    let mut pending = PENDING_PAGES.lock().await;
+    pending.remove(&chat_id);
+    record_backlog(&pending);
+    drop(pending);
+
+    if let Some(first) = pages.pop_front() {
+        send_page(bot, chat_id, first, !pages.is_empty()).await?;
+    }
+    if !pages.is_empty() {
+        let mut pending = PENDING_PAGES.lock().await;
+        pending.insert(chat_id, pages);
+        record_backlog(&pending);
+    }
+    Ok(())
+}
+
+pub async fn send_next_page(bot: &Bot, chat_id: ChatId) -> ResponseResult<()> {
+    let mut pending = PENDING_PAGES.lock().await;
+    let Some(pages) = pending.get_mut(&chat_id) else {
+        bot.send_message(chat_id, "Det finns inga fler annonser att visa.")
+            .await?;
+        return Ok(());
+    };
+    let next = pages.pop_front();
+    let has_more = !pages.is_empty();
+    if !has_more {
+        pending.remove(&chat_id);
+    }
+    record_backlog(&pending);
+    drop(pending);
+
+    if let Some(page) = next {
+        send_page(bot, chat_id, page, has_more).await?;
+    }
+    Ok(())
+}
+
+async fn send_page(bot: &Bot, chat_id: ChatId, page: String, has_more: bool) -> ResponseResult<()> {
+    let request = bot.send_message(chat_id, page);
+    if has_more {
+        let keyboard = InlineKeyboardMarkup::new(vec![vec![InlineKeyboardButton::callback(
+            "Visa fler ➡️",
+            CALLBACK_SHOW_MORE,
+        )]]);
+        request.reply_markup(keyboard).await?;
+    } else {
+        request.await?;
+    }
+    metrics::BOT_MESSAGES_SENT
+        .with_label_values(&["digest"])
+        .inc();
+    Ok(())
+}
+
+fn record_backlog(pending: &HashMap<ChatId, VecDeque<String>>) {
+    let pages = pending.values().map(VecDeque::len).sum::<usize>();
+    metrics::NOTIFICATION_BACKLOG.set(pages as i64);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::find_jobs::Region;
+    use crate::test_fixtures::{job, user};
+
+    #[test]
+    fn weekly_digests_are_due_a_week_after_the_last_at_the_chosen_hour() {
+        let mut subscriber = Subscriber {
+            user_id: "1".to_string(),
+            selections: UserSelections::default(),
+            frequency: DeliveryFrequency::Weekly,
+            delivery_hour: Some(8),
+            last_delivered_at: None,
+        };
+        let at = |date: &str| {
+            DateTime::parse_from_rfc3339(date)
+                .unwrap()
+                .with_timezone(&Stockholm)
+        };
+
+        assert!(is_due(&subscriber, &at("2026-10-14T08:05:00+02:00")));
+        assert!(!is_due(&subscriber, &at("2026-10-14T09:05:00+02:00")));
+
+        subscriber.last_delivered_at = Some("2026-10-14T06:05:00Z".to_string());
+        assert!(!is_due(&subscriber, &at("2026-10-20T08:05:00+02:00")));
+        assert!(is_due(&subscriber, &at("2026-10-21T08:05:00+02:00")));
+        // Missed on the 21st, sent on the 22nd
+        assert!(is_due(&subscriber, &at("2026-10-22T08:05:00+02:00")));
+    }
+
+    #[test]
+    fn user_without_selections_matches_everything() {
+        let selections = user("1").build().selections;
+        assert!(matches_selections(&job("1").build_db(), &selections));
+    }
+
+    #[test]
+    fn keyword_watches_filter_on_terms_and_employer() {
+        let selections = user("1")
+            .keywords("rust -senior @Spotify_AB")
+            .build()
+            .selections;
+
+        let matching = job("1")
+            .title("Rustutvecklare")
+            .employer("Spotify AB")
+            .build_db();
+        let senior = job("2")
+            .title("Senior Rustutvecklare")
+            .employer("Spotify AB")
+            .build_db();
+        let other_employer = job("3")
+            .title("Rustutvecklare")
+            .employer("Klarna")
+            .build_db();
+        let in_description = job("4")
+            .title("Backendutvecklare")
+            .employer("Spotify AB")
+            .description("Du har erfarenhet av Rust och Go")
+            .build_db();
+        let part_of_word = job("5")
+            .title("Rustik restaurang söker kock")
+            .employer("Spotify AB")
+            .build_db();
+
+        assert!(matches_selections(&matching, &selections));
+        assert!(!matches_selections(&senior, &selections));
+        assert!(!matches_selections(&other_employer, &selections));
+        assert!(matches_selections(&in_description, &selections));
+        assert!(!matches_selections(&part_of_word, &selections));
+    }
+
+    #[test]
+    fn occupation_selections_match_the_field_of_the_occupation_name() {
+        let selections = user("1")
+            .occupation(OccupationType::DataIT)
+            .build()
+            .selections;
+
+        let developer = job("1").occupation("MJUKVARUUTVECKLARE").build_db();
+        let cook = job("2").occupation("Kock").build_db();
+        let unknown = job("3").occupation("Okänt yrke").build_db();
+
+        assert!(matches_selections(&developer, &selections));
+        assert!(!matches_selections(&cook, &selections));
+        assert!(!matches_selections(&unknown, &selections));
+    }
+
+    #[test]
+    fn municipality_selections_narrow_down_the_region() {
+        let lund = user("1").municipality("muSY_tsR_vDZ").build().selections;
+        let skane = user("2").region(Region::Skåne).build().selections;
+
+        let in_lund = job("1").workplace("Lund").build_db();
+        let in_malmo = job("2").workplace("Malmö").build_db();
+
+        assert!(matches_selections(&in_lund, &lund));
+        assert!(!matches_selections(&in_malmo, &lund));
+        assert!(matches_selections(&in_lund, &skane));
+        assert!(matches_selections(&in_malmo, &skane));
+    }
+
+    #[test]
+    fn digest_is_grouped_by_occupation_and_split_into_pages() {
+        let jobs = (0..60)
+            .map(|i| {
+                let occupation = if i % 2 == 0 {
+                    "Lärare"
+                } else {
+                    "Mjukvaruutvecklare"
+                };
+                job(&i.to_string())
+                    .occupation(occupation)
+                    .title(&"Annonsrubrik ".repeat(5))
+                    .build_db()
+            })
+            .collect::<Vec<_>>();
+
+        let pages = build_digest_pages(&jobs, DeliveryFrequency::Daily);
+
+        assert!(pages.len() > 1);
+        assert!(pages
+            .iter()
+            .all(|page| page.chars().count() <= MAX_DIGEST_LENGTH));
+        let digest = pages.concat();
+        assert!(digest.starts_with("📅 Dagens sammanfattning: 60 nya annonser"));
+        assert!(
+            digest.find("💼 Lärare (30)").unwrap()
+                < digest.find("💼 Mjukvaruutvecklare (30)").unwrap()
+        );
+    }
+}
@@ -0,0 +1,275 @@
+//! Occupation and region taxonomy from the JobTech taxonomy API.
+use crate::constants::{TAXONOMY_PATH, URL_TAXONOMY_GRAPHQL};
+use crate::find_jobs::OccupationType;
+use crate::logging::info;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use strum::IntoEnumIterator;
+use strum_macros::{Display, EnumString};
+
+static TAXONOMY: OnceLock<Taxonomy> = OnceLock::new();
+static OCCUPATION_FIELDS: OnceLock<HashMap<String, OccupationType>> = OnceLock::new();
+static OCCUPATION_NAMES: OnceLock<HashMap<String, OccupationType>> = OnceLock::new();
+
+const TAXONOMY_QUERY: &str = r#"
+query {
+  occupations: concepts(type: "occupation-field") {
+    id
+    preferred_label
+    narrower(type: "ssyk-level-4") {
+      id
+      preferred_label
+      ssyk_code_2012
+      narrower(type: "occupation-name") {
+        id
+        preferred_label
+      }
+    }
+  }
+  regions: concepts(type: "region") {
+    id
+    preferred_label
+    national_nuts_level_3_code_2019
+    narrower(type: "municipality") {
+      id
+      preferred_label
+      lau_2_code_2015
+    }
+  }
+}
+"#;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, Display, EnumString)]
+#[strum(serialize_all = "kebab-case")]
+#[serde(rename_all = "kebab-case")]
+pub enum ConceptType {
+    OccupationField,
+    OccupationGroup,
+    OccupationName,
+    Region,
+    Municipality,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Concept {
+    pub id: String,
+    pub label: String,
+    #[serde(rename = "type")]
+    pub concept_type: ConceptType,
+    pub parent: Option<String>,
+    pub code: Option<String>,
+}
+
+#[derive(Default, Debug, Serialize, Deserialize)]
+pub struct Taxonomy {
+    pub concepts: Vec<Concept>,
+}
+
+impl Taxonomy {
+    pub fn by_id(&self, id: &str) -> Option<&Concept> {
+        self.concepts.iter().find(|concept| concept.id == id)
+    }
+
+    pub fn by_label(&self, concept_type: ConceptType, label: &str) -> Option<&Concept> {
+        let label = label.trim().to_lowercase();
+        self.of_type(concept_type)
+            .find(|concept| concept.label.to_lowercase() == label)
+    }
+
+    pub fn by_code(&self, concept_type: ConceptType, code: &str) -> Option<&Concept> {
+        self.of_type(concept_type)
+            .find(|concept| concept.code.as_deref() == Some(code))
+    }
+
+    pub fn of_type(&self, concept_type: ConceptType) -> impl Iterator<Item = &Concept> {
+        self.concepts
+            .iter()
+            .filter(move |concept| concept.concept_type == concept_type)
+    }
+
+    pub fn children(&self, parent_id: &str) -> impl Iterator<Item = &Concept> {
+        let parent_id = parent_id.to_string();
+        self.concepts
+            .iter()
+            .filter(move |concept| concept.parent.as_deref() == Some(parent_id.as_str()))
+    }
+
+    pub fn parent(&self, concept: &Concept) -> Option<&Concept> {
+        concept.parent.as_deref().and_then(|id| self.by_id(id))
+    }
+
+    // Walks up the hierarchy until a concept of the given type is found
+    pub fn ancestor(&self, concept: &Concept, concept_type: ConceptType) -> Option<&Concept> {
+        let mut current = self.parent(concept);
+        while let Some(concept) = current {
+            if concept.concept_type == concept_type {
+                return Some(concept);
+            }
+            current = self.parent(concept);
+        }
+        None
+    }
+}
+
+/// The taxonomy snapshot. Panics when it cannot be loaded, since every region, municipality and
+/// occupation lookup would silently find nothing without it. Binaries call [`init_taxonomy`] at
+/// start up to report a missing snapshot as an error instead.
+pub fn taxonomy() -> &'static Taxonomy {
+    TAXONOMY.get_or_init(|| match load_taxonomy() {
+        Ok(taxonomy) => taxonomy,
+        Err(e) => panic!("{}", e),
+    })
+}
+
+/// Occupation field of every occupation field concept id.
+pub fn occupation_fields() -> &'static HashMap<String, OccupationType> {
+    OCCUPATION_FIELDS.get_or_init(|| {
+        OccupationType::iter()
+            .filter_map(|occupation| Some((occupation.concept_id()?.to_string(), occupation)))
+            .collect()
+    })
+}
+
+/// Occupation field of every occupation name, keyed by the lowercase name, so matching ads
+/// against watches does not walk the taxonomy for every ad.
+pub fn occupation_names() -> &'static HashMap<String, OccupationType> {
+    OCCUPATION_NAMES.get_or_init(|| {
+        let taxonomy = taxonomy();
+        taxonomy
+            .of_type(ConceptType::OccupationName)
+            .filter_map(|concept| {
+                let field = taxonomy.ancestor(concept, ConceptType::OccupationField)?;
+                let occupation = occupation_fields().get(&field.id)?;
+                Some((concept.label.to_lowercase(), occupation.clone()))
+            })
+            .collect()
+    })
+}
+
+/// Loads the taxonomy snapshot unless it is already loaded.
+pub fn init_taxonomy() -> Result<(), Box<dyn std::error::Error>> {
+    if TAXONOMY.get().is_none() {
+        let _ = TAXONOMY.set(load_taxonomy()?);
+    }
+    Ok(())
+}
+
+#[cfg(not(test))]
+fn load_taxonomy() -> Result<Taxonomy, String> {
+    let error = |e: &dyn std::fmt::Display| {
+        format!(
+            "Failed to load taxonomy from {}, run refresh-taxonomy first: {}",
+            TAXONOMY_PATH, e
+        )
+    };
+    let content = std::fs::read_to_string(TAXONOMY_PATH).map_err(|e| error(&e))?;
+    let taxonomy: Taxonomy = serde_json::from_str(&content).map_err(|e| error(&e))?;
+    if taxonomy.concepts.is_empty() {
+        return Err(error(&"the snapshot has no concepts"));
+    }
+    Ok(taxonomy)
+}
+
+// Tests use a small bundled snapshot instead of the one refresh-taxonomy downloads
+#[cfg(test)]
+fn load_taxonomy() -> Result<Taxonomy, String> {
+    serde_json::from_str(include_str!("testdata/taxonomy.json")).map_err(|e| e.to_string())
+}
+
+#[derive(Deserialize, Debug)]
+struct GraphQlResponse {
+    data: GraphQlData,
+}
+
+#[derive(Deserialize, Debug)]
+struct GraphQlData {
+    occupations: Vec<GraphQlConcept>,
+    regions: Vec<GraphQlConcept>,
+}
+
+#[derive(Deserialize, Debug)]
+struct GraphQlConcept {
+    id: String,
+    preferred_label: String,
+    ssyk_code_2012: Option<String>,
+    national_nuts_level_3_code_2019: Option<String>,
+    lau_2_code_2015: Option<String>,
+    #[serde(default)]
+    narrower: Vec<GraphQlConcept>,
+}
+
+fn flatten(
+    concepts: Vec<GraphQlConcept>,
+    types: &[ConceptType],
+    parent: Option<&str>,
+    out: &mut Vec<Concept>,
+) {
+    let Some((concept_type, narrower_types)) = types.split_first() else {
+        return;
+    };
+    for concept in concepts {
+        let code = concept
+            .ssyk_code_2012
+            .or(concept.national_nuts_level_3_code_2019)
+            .or(concept.lau_2_code_2015);
+        out.push(Concept {
+            id: concept.id.clone(),
+            label: concept.preferred_label,
+            concept_type: *concept_type,
+            parent: parent.map(str::to_string),
+            code,
+        });
+        flatten(concept.narrower, narrower_types, Some(&concept.id), out);
+    }
+}
+
+pub async fn refresh_taxonomy() -> Result<usize, Box<dyn std::error::Error>> {
+    let response: GraphQlResponse = Client::new()
+        .get(URL_TAXONOMY_GRAPHQL)
+        .query(&[("query", TAXONOMY_QUERY)])
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    // The region type also contains regions outside Sweden, only Swedish län have a NUTS code
+    let regions = response
+        .data
+        .regions
+        .into_iter()
+        .filter(|region| region.national_nuts_level_3_code_2019.is_some())
+        .collect();
+
+    let mut concepts = Vec::new();
+    flatten(
+        response.data.occupations,
+        &[
+            ConceptType::OccupationField,
+            ConceptType::OccupationGroup,
+            ConceptType::OccupationName,
+        ],
+        None,
+        &mut concepts,
+    );
+    flatten(
+        regions,
+        &[ConceptType::Region, ConceptType::Municipality],
+        None,
+        &mut concepts,
+    );
+
+    if let Some(parent) = std::path::Path::new(TAXONOMY_PATH).parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let taxonomy = Taxonomy { concepts };
+    std::fs::write(TAXONOMY_PATH, serde_json::to_string_pretty(&taxonomy)?)?;
+    info!(
+        "Saved {} taxonomy concepts to {}",
+        taxonomy.concepts.len(),
+        TAXONOMY_PATH
+    );
+    Ok(taxonomy.concepts.len())
+}
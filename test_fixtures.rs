@@ -0,0 +1,147 @@
+use crate::bot::{KeywordWatch, UserSelections};
+use crate::constants::URL_JOB_ADS;
+use crate::context::{AppContext, Config};
+use crate::database::{DbJobAd, User};
+use crate::find_jobs::{JobAd, OccupationType, Region};
+use crate::fixtures::FixtureMode;
+use crate::store::SqliteStore;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+pub struct JobBuilder {
+    job: JobAd,
+}
+
+pub fn job(id: &str) -> JobBuilder {
+    JobBuilder {
+        job: JobAd {
+            id: Some(id.to_string()),
+            title: Some("Systemutvecklare".to_string()),
+            occupation: Some("Mjukvaruutvecklare".to_string()),
+            workplace: Some("Stockholm".to_string()),
+            workplace_name: Some("Testbolaget AB".to_string()),
+            published_date: Some("2026-10-18".to_string()),
+            last_application_date: Some("2026-11-18".to_string()),
+            source: Some("Platsbanken".to_string()),
+            url: Some(format!("{}{}", URL_JOB_ADS, id)),
+            source_links: None,
+            created_at: Some("2026-10-18T08:00:00.000Z".to_string()),
+            description: None,
+        },
+    }
+}
+
+impl JobBuilder {
+    pub fn title(mut self, title: &str) -> Self {
+        self.job.title = Some(title.to_string());
+        self
+    }
+
+    pub fn occupation(mut self, occupation: &str) -> Self {
+        self.job.occupation = Some(occupation.to_string());
+        self
+    }
+
+    pub fn workplace(mut self, workplace: &str) -> Self {
+        self.job.workplace = Some(workplace.to_string());
+        self
+    }
+
+    pub fn employer(mut self, employer: &str) -> Self {
+        self.job.workplace_name = Some(employer.to_string());
+        self
+    }
+
+    pub fn description(mut self, description: &str) -> Self {
+        self.job.description = Some(description.to_string());
+        self
+    }
+
+    pub fn created_at(mut self, created_at: &str) -> Self {
+        self.job.created_at = Some(created_at.to_string());
+        self
+    }
+
+    pub fn build(self) -> JobAd {
+        self.job
+    }
+
+    pub fn build_db(self) -> DbJobAd {
+        let url = self.job.url.clone();
+        DbJobAd {
+            url,
+            ..DbJobAd::from(self.job)
+        }
+    }
+}
+
+pub struct UserBuilder {
+    user: User,
+}
+
+pub fn user(id: &str) -> UserBuilder {
+    UserBuilder {
+        user: User {
+            id: id.to_string(),
+            selections: UserSelections::new(),
+        },
+    }
+}
+
+impl UserBuilder {
+    pub fn occupation(mut self, occupation: OccupationType) -> Self {
+        self.user
+            .selections
+            .selected_occupations
+            .get_or_insert_with(HashSet::new)
+            .insert(occupation);
+        self
+    }
+
+    pub fn region(mut self, region: Region) -> Self {
+        self.user
+            .selections
+            .selected_regions
+            .get_or_insert_with(HashSet::new)
+            .insert(region);
+        self
+    }
+
+    pub fn municipality(mut self, municipality_id: &str) -> Self {
+        self.user
+            .selections
+            .selected_municipalities
+            .get_or_insert_with(HashSet::new)
+            .insert(municipality_id.to_string());
+        self
+    }
+
+    pub fn keywords(mut self, terms: &str) -> Self {
+        let watch = KeywordWatch::parse(terms).expect("Invalid keyword watch in fixture");
+        self.user
+            .selections
+            .keyword_watches
+            .get_or_insert_with(Vec::new)
+            .push(watch);
+        self
+    }
+
+    pub fn build(self) -> User {
+        self.user
+    }
+}
+
+/// A context backed by a fresh in-memory database, for code that never calls the search API.
+pub async fn context() -> AppContext {
+    let store = SqliteStore::in_memory()
+        .await
+        .expect("Failed to open in-memory database");
+    let config = Config {
+        database_url: "sqlite::memory:".to_string(),
+        api_url: "http://localhost".to_string(),
+        fixture_mode: FixtureMode::Live,
+        http_addr: None,
+        api_key: None,
+    };
+    AppContext::with_store(Arc::new(store), config)
+}
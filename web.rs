@@ -0,0 +1,537 @@
+//! Server-rendered page for browsing, filtering and exporting stored ads, served by
+//! [`crate::server`].
+use crate::api::{ListParams, SortOrder};
+use crate::context::AppContext;
+use crate::database::{DbJobAd, GroupCount, JobField, JobListing, JobPage, JobSort};
+use crate::export::write_csv;
+use crate::find_jobs::Region;
+use crate::language::Language;
+use crate::logging::error;
+use crate::work_mode::WorkMode;
+use axum::extract::{Query, Request, State};
+use axum::http::header::{AUTHORIZATION, CONTENT_DISPOSITION, CONTENT_TYPE, WWW_AUTHENTICATE};
+use axum::http::StatusCode;
+use axum::middleware::{self, Next};
+use axum::response::{Html, IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use std::fmt::Write;
+use strum::IntoEnumIterator;
+
+/// Most ads written by one export.
+pub const EXPORT_LIMIT: i64 = 50_000;
+// Distinct occupations and sources offered in the filter dropdowns
+const FILTER_OPTION_LIMIT: i64 = 200;
+
+const COLUMNS: [(&str, JobSort); 7] = [
+    ("Titel", JobSort::Title),
+    ("Arbetsgivare", JobSort::Employer),
+    ("Ort", JobSort::Workplace),
+    ("Yrke", JobSort::Occupation),
+    ("Källa", JobSort::Source),
+    ("Publicerad", JobSort::Published),
+    ("Sista ansökningsdag", JobSort::LastApplication),
+];
+
+const STYLE: &str = "body{font-family:sans-serif;margin:2em}\
+form{display:flex;flex-wrap:wrap;gap:.5em;align-items:end;margin-bottom:1em}\
+label{display:flex;flex-direction:column;font-size:.85em}\
+table{border-collapse:collapse;width:100%}\
+th,td{text-align:left;padding:.3em .6em;border-bottom:1px solid #ddd}\
+th a{color:inherit}nav{margin:1em 0;display:flex;gap:1em}";
+
+pub fn routes(ctx: &AppContext) -> Router<AppContext> {
+    Router::new()
+        .route("/", get(index))
+        .route("/export.csv", get(export_csv))
+        .route("/export.json", get(export_json))
+        .route_layer(middleware::from_fn_with_state(ctx.clone(), require_login))
+}
+
+/// Browsers cannot send the `X-Api-Key` header, so when `API_KEY` is set the page and the
+/// exports ask for HTTP Basic credentials instead, with any user name and the key as password.
+async fn require_login(State(ctx): State<AppContext>, request: Request, next: Next) -> Response {
+    let Some(expected) = ctx.config.api_key.as_deref() else {
+        return next.run(request).await;
+    };
+    let password = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Basic "))
+        .and_then(decode_base64)
+        .and_then(|credentials| {
+            credentials
+                .split_once(':')
+                .map(|(_, password)| password.to_string())
+        });
+    if password.as_deref() == Some(expected) {
+        next.run(request).await
+    } else {
+        (
+            StatusCode::UNAUTHORIZED,
+            [(
+                WWW_AUTHENTICATE,
+                "Basic realm=\"platsbanken\", charset=\"UTF-8\"",
+            )],
+            "Inloggning krävs",
+        )
+            .into_response()
+    }
+}
+
+fn decode_base64(text: &str) -> Option<String> {
+    let mut bytes = Vec::new();
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for c in text.trim().trim_end_matches('=').bytes() {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return None,
+        };
+        buffer = (buffer << 6) | u32::from(value);
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+fn database_error(e: sqlx::Error) -> Response {
+    error!("Web UI database error: {:?}", e);
+    (StatusCode::INTERNAL_SERVER_ERROR, "Databasfel").into_response()
+}
+
+fn bad_request(message: String) -> Response {
+    (StatusCode::BAD_REQUEST, message).into_response()
+}
+
+async fn index(State(ctx): State<AppContext>, Query(params): Query<ListParams>) -> Response {
+    let listing = match params.to_listing() {
+        Ok(listing) => listing,
+        Err(message) => return bad_request(message),
+    };
+    let page = match ctx.store.list_jobs(&listing).await {
+        Ok(page) => page,
+        Err(e) => return database_error(e),
+    };
+    let occupations = ctx
+        .store
+        .count_jobs_by(JobField::Occupation, FILTER_OPTION_LIMIT)
+        .await
+        .unwrap_or_default();
+    let sources = ctx
+        .store
+        .count_jobs_by(JobField::Source, FILTER_OPTION_LIMIT)
+        .await
+        .unwrap_or_default();
+
+    Html(render_page(
+        &params,
+        &listing,
+        &page,
+        &occupations,
+        &sources,
+    ))
+    .into_response()
+}
+
+async fn export_jobs(ctx: &AppContext, params: &ListParams) -> Result<Vec<DbJobAd>, Response> {
+    let mut listing = params.to_listing().map_err(bad_request)?;
+    listing.limit = EXPORT_LIMIT;
+    listing.offset = 0;
+    let page = ctx
+        .store
+        .list_jobs(&listing)
+        .await
+        .map_err(database_error)?;
+    Ok(page.jobs)
+}
+
+async fn export_csv(
+    State(ctx): State<AppContext>,
+    Query(params): Query<ListParams>,
+) -> Result<Response, Response> {
+    let jobs = export_jobs(&ctx, &params).await?;
+    let mut body = Vec::new();
+    write_csv(&jobs, &mut body).map_err(database_error)?;
+    Ok((
+        [
+            (CONTENT_TYPE, "text/csv; charset=utf-8"),
+            (CONTENT_DISPOSITION, "attachment; filename=\"jobs.csv\""),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+async fn export_json(
+    State(ctx): State<AppContext>,
+    Query(params): Query<ListParams>,
+) -> Result<Response, Response> {
+    let jobs = export_jobs(&ctx, &params).await?;
+    Ok((
+        [(CONTENT_DISPOSITION, "attachment; filename=\"jobs.json\"")],
+        Json(jobs),
+    )
+        .into_response())
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// Link to `path` with the query string of `params`, escaped for an attribute
+fn link(path: &str, params: &ListParams) -> String {
+    let query = serde_urlencoded::to_string(params).unwrap_or_default();
+    escape(&format!("{}?{}", path, query))
+}
+
+fn select(
+    html: &mut String,
+    name: &str,
+    label: &str,
+    selected: &Option<String>,
+    options: &[String],
+) {
+    let selected = selected.as_deref().unwrap_or_default();
+    let _ = write!(
+        html,
+        r#"<label>{}<select name="{}"><option value="">Alla</option>"#,
+        label, name
+    );
+    for option in options {
+        let _ = write!(
+            html,
+            r#"<option value="{0}"{1}>{0}</option>"#,
+            escape(option),
+            if option == selected { " selected" } else { "" }
+        );
+    }
+    html.push_str("</select></label>");
+}
+
+fn group_values(groups: &[GroupCount]) -> Vec<String> {
+    let mut values = groups
+        .iter()
+        .filter_map(|group| group.value.clone())
+        .collect::<Vec<_>>();
+    values.sort();
+    values
+}
+
+fn render_page(
+    params: &ListParams,
+    listing: &JobListing,
+    page: &JobPage,
+    occupations: &[GroupCount],
+    sources: &[GroupCount],
+) -> String {
+    let mut html = String::new();
+    let _ = write!(
+        html,
+        "<!DOCTYPE html><html lang=\"sv\"><head><meta charset=\"utf-8\">\
+         <title>Platsbanken</title><style>{}</style></head><body><h1>Annonser</h1>",
+        STYLE
+    );
+
+    // Filters, the sort order is kept when filtering
+    let _ = write!(
+        html,
+        r#"<form method="get" action="/"><label>Sök<input type="search" name="q" value="{}">"#,
+        escape(params.q.as_deref().unwrap_or_default())
+    );
+    html.push_str("</label>");
+    let regions = Region::iter()
+        .map(|region| format!("{:?}", region))
+        .collect::<Vec<_>>();
+    select(&mut html, "region", "Region", &params.region, &regions);
+    select(
+        &mut html,
+        "occupation",
+        "Yrke",
+        &params.occupation,
+        &group_values(occupations),
+    );
+    select(
+        &mut html,
+        "source",
+        "Källa",
+        &params.source,
+        &group_values(sources),
+    );
+    let languages = Language::iter()
+        .map(|language| language.to_string())
+        .collect::<Vec<_>>();
+    select(&mut html, "language", "Språk", &params.language, &languages);
+    let work_modes = WorkMode::iter()
+        .map(|work_mode| work_mode.to_string())
+        .collect::<Vec<_>>();
+    select(
+        &mut html,
+        "work_mode",
+        "Arbetsplats",
+        &params.work_mode,
+        &work_modes,
+    );
+    for (name, label, value) in [
+        ("created_after", "Från", &params.created_after),
+        ("created_before", "Till", &params.created_before),
+    ] {
+        let _ = write!(
+            html,
+            r#"<label>{}<input type="date" name="{}" value="{}"></label>"#,
+            label,
+            name,
+            escape(value.as_deref().unwrap_or_default())
+        );
+    }
+    let _ = write!(
+        html,
+        r#"<label>Minimilön<input type="number" name="min_salary" min="0" step="1000" value="{}"></label>"#,
+        escape(params.min_salary.as_deref().unwrap_or_default())
+    );
+    for (name, value) in [
+        ("sort", params.sort.to_string()),
+        ("order", params.order.to_string()),
+    ] {
+        let _ = write!(
+            html,
+            r#"<input type="hidden" name="{}" value="{}">"#,
+            name, value
+        );
+    }
+    html.push_str(r#"<button type="submit">Filtrera</button><a href="/">Rensa</a></form>"#);
+
+    // Exports cover every ad matching the filters, not only this page
+    let filters = ListParams {
+        limit: None,
+        offset: None,
+        ..params.clone()
+    };
+    let first = if page.jobs.is_empty() {
+        0
+    } else {
+        listing.offset + 1
+    };
+    let _ = write!(
+        html,
+        "<nav><span>Visar {}–{} av {}</span>",
+        first,
+        listing.offset + page.jobs.len() as i64,
+        page.total
+    );
+    let _ = write!(
+        html,
+        r#"<a href="{}">Exportera CSV</a><a href="{}">Exportera JSON</a></nav>"#,
+        link("/export.csv", &filters),
+        link("/export.json", &filters)
+    );
+
+    html.push_str("<table><thead><tr>");
+    for (label, sort) in COLUMNS {
+        let current = listing.sort == sort;
+        let order = if current && listing.descending {
+            SortOrder::Asc
+        } else {
+            SortOrder::Desc
+        };
+        let sorted = ListParams {
+            sort,
+            order,
+            offset: None,
+            ..params.clone()
+        };
+        let arrow = match (current, listing.descending) {
+            (false, _) => "",
+            (true, true) => " ▼",
+            (true, false) => " ▲",
+        };
+        let _ = write!(
+            html,
+            r#"<th><a href="{}">{}{}</a></th>"#,
+            link("/", &sorted),
+            label,
+            arrow
+        );
+    }
+    html.push_str("</tr></thead><tbody>");
+    for job in &page.jobs {
+        let field = |value: &Option<String>| escape(value.as_deref().unwrap_or_default());
+        let title = match &job.url {
+            Some(url) => format!(
+                r#"<a href="{}" target="_blank" rel="noopener">{}</a>"#,
+                escape(url),
+                field(&job.title)
+            ),
+            None => field(&job.title),
+        };
+        let cells = [
+            title,
+            field(&job.workplace_name),
+            field(&job.workplace),
+            field(&job.occupation),
+            field(&job.source),
+            field(&job.published_date),
+            field(&job.last_application_date),
+        ];
+        let _ = write!(html, "<tr><td>{}</td></tr>", cells.join("</td><td>"));
+    }
+    html.push_str("</tbody></table><nav>");
+
+    if listing.offset > 0 {
+        let previous = ListParams {
+            offset: Some((listing.offset - listing.limit).max(0)),
+            ..params.clone()
+        };
+        let _ = write!(
+            html,
+            r#"<a href="{}">« Föregående</a>"#,
+            link("/", &previous)
+        );
+    }
+    if listing.offset + listing.limit < page.total {
+        let next = ListParams {
+            offset: Some(listing.offset + listing.limit),
+            ..params.clone()
+        };
+        let _ = write!(html, r#"<a href="{}">Nästa »</a>"#, link("/", &next));
+    }
+    html.push_str("</nav></body></html>");
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::{context, job};
+    use axum::body::{to_bytes, Body};
+    use tower::ServiceExt;
+
+    async fn get(ctx: &AppContext, uri: &str) -> (StatusCode, String, String) {
+        get_as(ctx, uri, None).await
+    }
+
+    async fn get_as(
+        ctx: &AppContext,
+        uri: &str,
+        authorization: Option<&str>,
+    ) -> (StatusCode, String, String) {
+        let mut request = axum::http::Request::builder().uri(uri);
+        if let Some(authorization) = authorization {
+            request = request.header(AUTHORIZATION, authorization);
+        }
+        let response = routes(ctx)
+            .with_state(ctx.clone())
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let content_type = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default()
+            .to_string();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (
+            status,
+            content_type,
+            String::from_utf8(body.to_vec()).unwrap(),
+        )
+    }
+
+    async fn stored_context() -> AppContext {
+        let ctx = context().await;
+        ctx.store
+            .insert_jobs(&[
+                job("1")
+                    .title("Rust & <Go>")
+                    .workplace("Malmö")
+                    .description("Fully remote")
+                    .build(),
+                job("2").title("Lärare").workplace("Kiruna").build(),
+            ])
+            .await
+            .unwrap();
+        ctx
+    }
+
+    #[tokio::test]
+    async fn page_lists_filtered_ads_escaped() {
+        let ctx = stored_context().await;
+        let (status, _, html) = get(&ctx, "/?region=Sk%C3%A5ne&sort=title&order=asc").await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(html.contains("Rust &amp; &lt;Go&gt;"));
+        assert!(!html.contains("Lärare</a>"));
+        assert!(html.contains("Visar 1–1 av 1"));
+        assert!(html.contains(r#"<option value="Skåne" selected>"#));
+    }
+
+    #[tokio::test]
+    async fn exports_every_matching_ad() {
+        let ctx = stored_context().await;
+        let (status, content_type, csv) = get(&ctx, "/export.csv?q=l%C3%A4rare&limit=1").await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(content_type.starts_with("text/csv"));
+        assert_eq!(csv.lines().count(), 2);
+        assert!(csv.contains("Lärare"));
+
+        let (_, content_type, json) = get(&ctx, "/export.json").await;
+        assert_eq!(content_type, "application/json");
+        let jobs: Vec<serde_json::Value> = serde_json::from_str(&json).unwrap();
+        assert_eq!(jobs.len(), 2);
+
+        let (_, _, json) = get(&ctx, "/export.json?language=sv").await;
+        let jobs: Vec<serde_json::Value> = serde_json::from_str(&json).unwrap();
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0]["language"], "sv");
+
+        let (_, _, json) = get(&ctx, "/export.json?work_mode=remote").await;
+        let jobs: Vec<serde_json::Value> = serde_json::from_str(&json).unwrap();
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0]["id"], "1");
+
+        let (status, _, _) = get(&ctx, "/export.json?region=Atlantis").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn page_and_exports_ask_for_the_api_key_as_password_when_configured() {
+        let mut ctx = stored_context().await;
+        let mut config = (*ctx.config).clone();
+        config.api_key = Some("hemlig".to_string());
+        ctx.config = std::sync::Arc::new(config);
+
+        for uri in ["/", "/export.csv", "/export.json"] {
+            let (status, _, _) = get(&ctx, uri).await;
+            assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+            // user:fel
+            let (status, _, _) = get_as(&ctx, uri, Some("Basic dXNlcjpmZWw=")).await;
+            assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+            // user:hemlig
+            let (status, _, _) = get_as(&ctx, uri, Some("Basic dXNlcjpoZW1saWc=")).await;
+            assert_eq!(status, StatusCode::OK);
+        }
+    }
+}
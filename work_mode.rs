@@ -0,0 +1,210 @@
+//! Classification of ads as on-site, hybrid or remote work from their workplace and the
+//! keywords in their title and description.
+use crate::context::AppContext;
+use crate::database::SearchQuery;
+use crate::logging::info;
+use crate::skills;
+use serde::{Deserialize, Serialize};
+use strum_macros::{Display, EnumIter, EnumString};
+
+const HYBRID_TERMS: [&str; 5] = [
+    "hybrid",
+    "delvis på distans",
+    "delvis hemifrån",
+    "delvis remote",
+    "partly remote",
+];
+
+const REMOTE_TERMS: [&str; 5] = [
+    "distans",
+    "remote",
+    "hemifrån",
+    "work from home",
+    "hemarbete",
+];
+
+// Terms saying that the whole job is remote, even when the ad names a workplace
+const FULLY_REMOTE_TERMS: [&str; 5] = [
+    "helt på distans",
+    "100% distans",
+    "fully remote",
+    "100% remote",
+    "remote first",
+];
+
+// Heads of compounds that still describe the work, as in "distansarbete". Distance education
+// such as "distansutbildning" says nothing about the workplace and "hybridbilar" nothing at all.
+const COMPOUND_HEADS: [&str; 4] = ["arbete", "jobb", "tjänst", "läge"];
+
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, Hash, Display, EnumString, EnumIter, Serialize, Deserialize,
+)]
+pub enum WorkMode {
+    #[strum(to_string = "on_site", serialize = "kontor")]
+    #[serde(rename = "on_site")]
+    OnSite,
+    #[strum(to_string = "hybrid")]
+    #[serde(rename = "hybrid")]
+    Hybrid,
+    #[strum(to_string = "remote", serialize = "distans")]
+    #[serde(rename = "remote")]
+    Remote,
+}
+
+impl WorkMode {
+    /// Ads mentioning hybrid work are hybrid. Ads mentioning remote work are remote when the
+    /// workplace is unspecified or the ad says all work is remote, and hybrid otherwise, since
+    /// an ad with a workplace usually offers some remote days. Ads without any of the keywords
+    /// are on-site, also when the workplace is unspecified.
+    pub fn classify(
+        workplace: Option<&str>,
+        title: Option<&str>,
+        description: Option<&str>,
+    ) -> WorkMode {
+        let workplace = workplace.unwrap_or_default().trim().to_lowercase();
+        let text = format!(
+            "{} {}",
+            title.unwrap_or_default(),
+            description.unwrap_or_default()
+        )
+        .to_lowercase();
+
+        let unspecified = workplace.is_empty() || workplace.contains("ospecificerad");
+        if mentions(&text, &HYBRID_TERMS) {
+            WorkMode::Hybrid
+        } else if mentions(&workplace, &REMOTE_TERMS)
+            || mentions(&text, &FULLY_REMOTE_TERMS)
+            || (unspecified && mentions(&text, &REMOTE_TERMS))
+        {
+            WorkMode::Remote
+        } else if mentions(&text, &REMOTE_TERMS) {
+            WorkMode::Hybrid
+        } else {
+            WorkMode::OnSite
+        }
+    }
+
+    /// Swedish term of the mode in bot watches.
+    pub fn keyword(self) -> &'static str {
+        match self {
+            WorkMode::OnSite => "kontor",
+            WorkMode::Hybrid => "hybrid",
+            WorkMode::Remote => "distans",
+        }
+    }
+}
+
+fn mentions(text: &str, terms: &[&str]) -> bool {
+    terms
+        .iter()
+        .any(|term| skills::mentions_word(text, term, &COMPOUND_HEADS))
+}
+
+/// The work mode of an ad with a fetched description. Ads without one are left unclassified,
+/// most ads only say how the work is done in the description.
+pub fn classify_ad(
+    workplace: Option<&str>,
+    title: Option<&str>,
+    description: Option<&str>,
+) -> Option<WorkMode> {
+    description.map(|description| WorkMode::classify(workplace, title, Some(description)))
+}
+
+/// Classifies every stored ad again, for ads stored before work modes were classified.
+pub async fn classify_work_modes(ctx: &AppContext) -> Result<usize, sqlx::Error> {
+    let jobs = ctx.store.query_jobs(SearchQuery::All).await?;
+    for job in jobs.iter() {
+        let Some(id) = job.id.as_deref() else {
+            continue;
+        };
+        let work_mode = classify_ad(
+            job.workplace.as_deref(),
+            job.title.as_deref(),
+            job.description.as_deref(),
+        );
+        if job.work_mode != work_mode.map(|work_mode| work_mode.to_string()) {
+            ctx.store.update_work_mode(id, work_mode).await?;
+        }
+    }
+    info!("Classified the work mode of {} ads", jobs.len());
+    Ok(jobs.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_from_keywords_and_workplace() {
+        assert_eq!(
+            WorkMode::classify(
+                Some("Stockholm"),
+                Some("Backendutvecklare"),
+                Some("Vi arbetar hybrid med två dagar på kontoret.")
+            ),
+            WorkMode::Hybrid
+        );
+        assert_eq!(
+            WorkMode::classify(None, Some("Remote Rust Developer"), None),
+            WorkMode::Remote
+        );
+        assert_eq!(
+            WorkMode::classify(
+                Some("Göteborg"),
+                None,
+                Some("Tjänsten utförs helt på distans.")
+            ),
+            WorkMode::Remote
+        );
+        assert_eq!(
+            WorkMode::classify(
+                Some("Malmö"),
+                None,
+                Some("Möjlighet att arbeta på distans vissa dagar.")
+            ),
+            WorkMode::Hybrid
+        );
+        assert_eq!(
+            WorkMode::classify(Some("Distansarbete"), Some("Kundtjänst"), None),
+            WorkMode::Remote
+        );
+    }
+
+    #[test]
+    fn keywords_only_count_as_whole_words() {
+        assert_eq!(
+            WorkMode::classify(
+                Some("Göteborg"),
+                Some("Mekaniker"),
+                Some("Du servar elhybrider och hybridbilar i vår verkstad.")
+            ),
+            WorkMode::OnSite
+        );
+        assert_eq!(
+            WorkMode::classify(None, Some("Distansjobb inom kundtjänst"), None),
+            WorkMode::Remote
+        );
+    }
+
+    #[test]
+    fn unspecified_workplace_alone_is_not_remote() {
+        assert_eq!(
+            WorkMode::classify(None, Some("Lagerarbetare"), None),
+            WorkMode::OnSite
+        );
+        assert_eq!(
+            WorkMode::classify(
+                Some("Umeå"),
+                Some("Lärare"),
+                Some("Du undervisar i vår distansutbildning.")
+            ),
+            WorkMode::OnSite
+        );
+        assert_eq!(
+            classify_ad(Some("Stockholm"), Some("Lagerarbetare"), None),
+            None
+        );
+        assert_eq!("distans".parse::<WorkMode>().unwrap(), WorkMode::Remote);
+        assert_eq!(WorkMode::OnSite.to_string(), "on_site");
+    }
+}